@@ -6,6 +6,7 @@ fn main() -> anyhow::Result<()> {
     config.boxed(".blockdaemon.solana.accountsdb_plugin_kafka.types.MessageWrapper");
     config.protoc_arg("--experimental_allow_proto3_optional");
     config.compile_protos(&["proto/event.proto"], &["proto/"])?;
+    println!("cargo:rerun-if-changed=proto/event.proto");
 
     // Version metrics
     let mut envs = vergen::EmitBuilder::builder();
@@ -8,7 +8,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::EnvConfig;
+use crate::{env_config::EnvConfigKafka, EnvConfig};
 
 use solana_geyser_plugin_interface::geyser_plugin_interface::{
     GeyserPluginError as PluginError, Result as PluginResult,
@@ -62,47 +62,84 @@ impl Allowlist {
     pub fn new_from_config(config: &EnvConfig) -> PluginResult<Self> {
         match config {
             EnvConfig::Kafka(config) => {
-                // Users can provide a URL to fetch the allow list from
-                if !config.program_allowlist_url.is_empty() {
-                    assert!(
-                        config.program_allowlist_slot_interval > 0,
-                        "program_allowlist_slot_interval must be greater than 0"
-                    );
-                    let mut this = Self::new_from_http(
-                        &config.program_allowlist_url.clone(),
-                        &config.program_allowlist_auth.clone(),
-                        config.program_allowlist_slot_interval,
-                    )
-                    .unwrap();
-
-                    if !config.program_allowlist.is_empty() {
-                        // The allowlist to start with can be defined in the config
-                        this.push_vec(config.program_allowlist.clone());
-                    } else {
-                        // Otherwise, fetch it from the provided url
-                        this.init_list_from_http_blocking(
-                            &config.program_allowlist_url,
-                            &config.program_allowlist_auth,
-                        )?;
-                    }
-
-                    return Ok(this);
-                }
-
                 // If no url is provided, then the allowlist needs to be defined in the config
-                if config.program_allowlist.is_empty() {
+                // unless all programs but those in the denylist are published
+                if config.program_allowlist_url.is_empty()
+                    && config.program_allowlist.is_empty()
+                    && config.program_denylist.is_empty()
+                    && config.program_denylist_url.is_empty()
+                {
                     return Err(PluginError::Custom(Box::new(SimpleError::new(
-                        "Need to provide a program allowlist provided or a URL to fetch it from"
+                        "Need to provide a program allowlist, a program denylist or a URL to \
+                         fetch either from"
                             .to_string(),
                     ))));
                 }
 
-                Self::new_from_vec(config.program_allowlist.clone())
+                Self::new_from_vec_or_http(
+                    &config.name,
+                    &config.program_allowlist,
+                    &config.program_allowlist_url,
+                    &config.program_allowlist_auth,
+                    config.program_allowlist_slot_interval,
+                )
             }
             EnvConfig::Local(config) => Self::new_from_vec(config.program_allowlist.clone()),
         }
     }
 
+    /// Creates the list of programs that are never published from the `program_denylist` and
+    /// `program_denylist_url` of [config].
+    pub fn new_denylist_from_config(config: &EnvConfig) -> PluginResult<Self> {
+        match config {
+            EnvConfig::Kafka(config) => Self::new_from_vec_or_http(
+                &config.name,
+                &config.program_denylist,
+                &config.program_denylist_url,
+                &config.program_allowlist_auth,
+                config.program_allowlist_slot_interval,
+            ),
+            EnvConfig::Local(config) => Self::new_from_vec_or_http(
+                &config.name,
+                &config.program_denylist,
+                &config.program_denylist_url,
+                "",
+                EnvConfigKafka::default_program_allowlist_slot_interval(),
+            ),
+        }
+    }
+
+    fn new_from_vec_or_http(
+        env: &str,
+        list: &[String],
+        url: &str,
+        auth: &str,
+        slot_interval: u64,
+    ) -> PluginResult<Self> {
+        // Users can provide a URL to fetch the list from
+        if url.is_empty() {
+            return Self::new_from_vec(list.to_vec());
+        }
+        if slot_interval == 0 {
+            return Err(PluginError::ConfigFileReadError {
+                msg: format!(
+                    "program_allowlist_slot_interval must be greater than 0 in {env} environment"
+                ),
+            });
+        }
+        let mut this = Self::new_from_http(url, auth, slot_interval)?;
+
+        if !list.is_empty() {
+            // The list to start with can be defined in the config
+            this.push_vec(list.to_vec());
+        } else {
+            // Otherwise, fetch it from the provided url
+            this.init_list_from_http_blocking(url, auth)?;
+        }
+
+        Ok(this)
+    }
+
     /// new_from_vec creates a new Allowlist from a vector of program ids.
     pub fn new_from_vec(program_allowlist: Vec<String>) -> PluginResult<Self> {
         let program_allowlist = program_allowlist
@@ -128,7 +165,7 @@ impl Allowlist {
 
     // fetch_remote_allowlist fetches the allowlist from the remote server,
     // and returns a HashSet of program ids.
-    fn fetch_remote_allowlist(url: &str, auth: &str) -> PluginResult<HashSet<[u8; 32]>> {
+    pub(crate) fn fetch_remote_allowlist(url: &str, auth: &str) -> PluginResult<HashSet<[u8; 32]>> {
         let mut program_allowlist = HashSet::new();
 
         let mut req = ureq::get(url);
@@ -265,6 +302,14 @@ impl Allowlist {
         })
     }
 
+    /// Returns whether [program] is in this list, regardless of whether it is empty.
+    pub fn contains(&self, program: &[u8]) -> bool {
+        let Ok(key) = <&[u8; 32]>::try_from(program) else {
+            return false;
+        };
+        self.list.lock().unwrap().contains(key)
+    }
+
     pub fn wants_program(&self, program: &[u8]) -> bool {
         let key = match <&[u8; 32]>::try_from(program) {
             Ok(key) => key,
@@ -283,8 +328,6 @@ impl Allowlist {
 mod tests {
     use std::{thread::sleep, time::Duration};
 
-    use crate::env_config::EnvConfigKafka;
-
     use super::*;
     #[test]
     fn test_allowlist_from_vec() {
@@ -357,6 +400,33 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_denylist_create_from_http() {
+        let _m = mockito::mock("GET", "/denylist.txt")
+            .with_status(200)
+            .with_header("content-type", "text/plain")
+            .with_body("{\"result\":[\"Vote111111111111111111111111111111111111111\"]}")
+            .create();
+
+        let config = EnvConfig::Kafka(EnvConfigKafka {
+            program_denylist_url: [mockito::server_url(), "/denylist.txt".to_string()].join(""),
+            ..EnvConfigKafka::default()
+        });
+
+        let denylist = Allowlist::new_denylist_from_config(&config).unwrap();
+        assert_eq!(denylist.len(), 1);
+        assert!(denylist.contains(
+            &Pubkey::from_str("Vote111111111111111111111111111111111111111")
+                .unwrap()
+                .to_bytes()
+        ));
+        assert!(!denylist.contains(
+            &Pubkey::from_str("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")
+                .unwrap()
+                .to_bytes()
+        ));
+    }
+
     fn wait_for_update_completion(allowlist: &Allowlist) {
         assert!(allowlist.updater.as_ref().unwrap().is_updating());
         while allowlist.updater.as_ref().unwrap().is_updating() {
@@ -385,6 +455,24 @@ mod tests {
         assert!(allowlist.needs_remote_update(10));
     }
 
+    #[test]
+    fn test_allowlist_zero_slot_interval() {
+        let config = EnvConfig::Kafka(EnvConfigKafka {
+            name: "dev".to_string(),
+            program_allowlist_url: "http://localhost:9999/allowlist.txt".to_string(),
+            program_allowlist_slot_interval: 0,
+            ..EnvConfigKafka::default()
+        });
+
+        match Allowlist::new_from_config(&config) {
+            Err(PluginError::ConfigFileReadError { msg }) => assert_eq!(
+                msg,
+                "program_allowlist_slot_interval must be greater than 0 in dev environment"
+            ),
+            _ => panic!("expected a config error"),
+        }
+    }
+
     #[test]
     fn test_allowlist_remote_upate_if_needed() {
         let _m = mockito::mock("GET", "/allowlist.txt")
@@ -0,0 +1,70 @@
+//! Validates a plugin config and prints the effective config with defaults applied and
+//! secrets redacted.
+//!
+//! Usage: `validate-config [--check-allowlist-urls] <config.json|yaml|toml>`
+//!
+//! Unknown keys, which the plugin ignores, and deprecated keys are printed as warnings.
+//! Exits with `1` if the config cannot be read or is invalid and with `2` on usage errors.
+
+use std::{env, process::ExitCode};
+
+use solana_accountsdb_plugin_kafka::Config;
+
+const USAGE: &str = "Usage: validate-config [--check-allowlist-urls] <config.json|yaml|toml>";
+
+fn main() -> ExitCode {
+    let mut check_allowlist_urls = false;
+    let mut config_path = None;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--check-allowlist-urls" => check_allowlist_urls = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ if config_path.is_none() && !arg.starts_with('-') => config_path = Some(arg),
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::from(2);
+            }
+        }
+    }
+    let Some(config_path) = config_path else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let config = match Config::read_with_warnings(&config_path) {
+        Ok((config, warnings)) => {
+            for warning in warnings {
+                eprintln!("Warning: {warning}");
+            }
+            config
+        }
+        Err(err) => {
+            eprintln!("Failed to read {config_path}: {err}");
+            return ExitCode::from(1);
+        }
+    };
+    if let Err(errors) = config.validate(check_allowlist_urls) {
+        eprintln!("{config_path} is invalid:");
+        for error in errors {
+            eprintln!("  - {error}");
+        }
+        return ExitCode::from(1);
+    }
+
+    match config
+        .to_redacted_json()
+        .and_then(|config| serde_json::to_string_pretty(&config))
+    {
+        Ok(config) => {
+            println!("{config}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Failed to print {config_path}: {err}");
+            ExitCode::from(1)
+        }
+    }
+}
@@ -35,6 +35,17 @@ impl Display for Cluster {
 }
 
 impl Cluster {
+    /// Name of the cluster as expanded in topic templates, i.e. `mainnet` or the name of a
+    /// custom cluster.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Mainnet => "mainnet",
+            Self::Devnet => "devnet",
+            Self::Testnet => "testnet",
+            Self::Custom(value) => value,
+        }
+    }
+
     /// Derives a key to be used for Kafka events from the provided [cluster] and [program_id].
     /// This schema to derive keys is concistently used across all Ironforge services when
     /// sending/receiving Kafka events.
@@ -0,0 +1,392 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_geyser_plugin_interface::geyser_plugin_interface::SlotStatus as PluginSlotStatus;
+
+use crate::{
+    prom::{COMMITMENT_BUFFERED_EVENTS, COMMITMENT_DISCARDED_EVENTS_TOTAL},
+    slot_tree::SlotTree,
+    AccountCreatedEvent, TransactionEvent, UpdateAccountEvent,
+};
+
+/// The commitment level a slot needs to reach before the account and transaction events of
+/// that slot are published.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    /// Publish events as soon as they are received.
+    #[default]
+    Processed,
+    /// Buffer events until their slot is confirmed.
+    Confirmed,
+    /// Buffer events until their slot is rooted.
+    Rooted,
+}
+
+impl Commitment {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Commitment::Processed => "processed",
+            Commitment::Confirmed => "confirmed",
+            Commitment::Rooted => "rooted",
+        }
+    }
+}
+
+impl From<PluginSlotStatus> for Commitment {
+    fn from(status: PluginSlotStatus) -> Self {
+        match status {
+            PluginSlotStatus::Processed => Commitment::Processed,
+            PluginSlotStatus::Confirmed => Commitment::Confirmed,
+            PluginSlotStatus::Rooted => Commitment::Rooted,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum BufferedEvent {
+    Account(UpdateAccountEvent),
+    Transaction(Box<TransactionEvent>),
+    AccountCreated(AccountCreatedEvent),
+}
+
+/// An event paired with the name of the environment to publish it to.
+pub type EnvEvent = (String, BufferedEvent);
+
+/// Holds the events of each slot until that slot reaches the [Commitment] of the environment
+/// the events are destined for.
+#[derive(Default)]
+pub struct CommitmentBuffer {
+    confirmed: Mutex<SlotEvents>,
+    rooted: Mutex<SlotEvents>,
+}
+
+impl CommitmentBuffer {
+    pub fn push(&self, slot: u64, commitment: Commitment, env: &str, event: BufferedEvent) {
+        let slot_events = match commitment {
+            Commitment::Processed => return,
+            Commitment::Confirmed => &self.confirmed,
+            Commitment::Rooted => &self.rooted,
+        };
+        slot_events
+            .lock()
+            .expect("commitment buffer mutex poisoned")
+            .push(slot, env, event);
+        COMMITMENT_BUFFERED_EVENTS
+            .with_label_values(&[commitment.as_str()])
+            .inc();
+    }
+
+    /// Removes and returns the events which can be published now that [slot] reached
+    /// [commitment], in slot order.
+    ///
+    /// Since a slot reaching a commitment level implies that all of its ancestors did as
+    /// well, the events of those ancestors are included.
+    /// Once [slot] is rooted, the events of older slots that are not its ancestors are
+    /// discarded since those slots are on abandoned forks.
+    pub fn flush(&self, slot: u64, commitment: Commitment, tree: &SlotTree) -> Vec<EnvEvent> {
+        match commitment {
+            Commitment::Processed => vec![],
+            Commitment::Confirmed => {
+                Self::flush_slot_events(&self.confirmed, Commitment::Confirmed, slot, false, tree)
+            }
+            Commitment::Rooted => {
+                let mut events = Self::flush_slot_events(
+                    &self.confirmed,
+                    Commitment::Confirmed,
+                    slot,
+                    true,
+                    tree,
+                );
+                events.extend(Self::flush_slot_events(
+                    &self.rooted,
+                    Commitment::Rooted,
+                    slot,
+                    true,
+                    tree,
+                ));
+                events
+            }
+        }
+    }
+
+    /// Discards the events of [slots] since those are on abandoned forks.
+    pub fn discard(&self, slots: &[u64]) {
+        for (slot_events, commitment) in [
+            (&self.confirmed, Commitment::Confirmed),
+            (&self.rooted, Commitment::Rooted),
+        ] {
+            let discarded = slot_events
+                .lock()
+                .expect("commitment buffer mutex poisoned")
+                .discard(slots);
+            let commitment = commitment.as_str();
+            COMMITMENT_BUFFERED_EVENTS
+                .with_label_values(&[commitment])
+                .sub(discarded as i64);
+            COMMITMENT_DISCARDED_EVENTS_TOTAL
+                .with_label_values(&[commitment])
+                .inc_by(discarded as u64);
+        }
+    }
+
+    /// Discards the events destined for [env] and returns how many were discarded.
+    pub fn discard_env(&self, env: &str) -> usize {
+        let mut total = 0;
+        for (slot_events, commitment) in [
+            (&self.confirmed, Commitment::Confirmed),
+            (&self.rooted, Commitment::Rooted),
+        ] {
+            let discarded = slot_events
+                .lock()
+                .expect("commitment buffer mutex poisoned")
+                .discard_env(env);
+            COMMITMENT_BUFFERED_EVENTS
+                .with_label_values(&[commitment.as_str()])
+                .sub(discarded as i64);
+            total += discarded;
+        }
+        total
+    }
+
+    fn flush_slot_events(
+        slot_events: &Mutex<SlotEvents>,
+        commitment: Commitment,
+        slot: u64,
+        is_rooted: bool,
+        tree: &SlotTree,
+    ) -> Vec<EnvEvent> {
+        let (flushed, discarded) = slot_events
+            .lock()
+            .expect("commitment buffer mutex poisoned")
+            .flush(slot, is_rooted, tree);
+        let commitment = commitment.as_str();
+        COMMITMENT_BUFFERED_EVENTS
+            .with_label_values(&[commitment])
+            .sub((flushed.len() + discarded) as i64);
+        COMMITMENT_DISCARDED_EVENTS_TOTAL
+            .with_label_values(&[commitment])
+            .inc_by(discarded as u64);
+        flushed
+    }
+}
+
+#[derive(Default)]
+struct SlotEvents {
+    slots: BTreeMap<u64, Vec<EnvEvent>>,
+}
+
+impl SlotEvents {
+    fn push(&mut self, slot: u64, env: &str, event: BufferedEvent) {
+        self.slots
+            .entry(slot)
+            .or_default()
+            .push((env.to_string(), event));
+    }
+
+    /// Removes the events of [slots] and returns how many were removed.
+    fn discard(&mut self, slots: &[u64]) -> usize {
+        slots
+            .iter()
+            .filter_map(|slot| self.slots.remove(slot))
+            .map(|events| events.len())
+            .sum()
+    }
+
+    /// Removes the events destined for [env] and returns how many were removed.
+    fn discard_env(&mut self, env: &str) -> usize {
+        let mut discarded = 0;
+        self.slots.retain(|_, events| {
+            let len = events.len();
+            events.retain(|(event_env, _)| event_env != env);
+            discarded += len - events.len();
+            !events.is_empty()
+        });
+        discarded
+    }
+
+    /// Returns the events of [slot] and its ancestors as well as the number of events that
+    /// were discarded.
+    fn flush(&mut self, slot: u64, is_rooted: bool, tree: &SlotTree) -> (Vec<EnvEvent>, usize) {
+        let mut flushed = vec![];
+        let mut discarded = 0;
+        let buffered_slots = self
+            .slots
+            .range(..=slot)
+            .map(|(s, _)| *s)
+            .collect::<Vec<_>>();
+        for buffered_slot in buffered_slots {
+            let publish = match tree.is_ancestor_or_self(buffered_slot, slot) {
+                Some(true) => true,
+                Some(false) if is_rooted => false,
+                None if is_rooted => {
+                    warn!(
+                        "Unable to tell if slot {} is an ancestor of rooted slot {}, publishing its events",
+                        buffered_slot, slot
+                    );
+                    true
+                }
+                // Keep waiting until the slot is either flushed or found to be on an abandoned
+                // fork once a slot is rooted
+                _ => continue,
+            };
+
+            let events = self.slots.remove(&buffered_slot).unwrap_or_default();
+            if publish {
+                flushed.extend(events);
+            } else {
+                discarded += events.len();
+            }
+        }
+        (flushed, discarded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_event(slot: u64) -> BufferedEvent {
+        BufferedEvent::Account(UpdateAccountEvent {
+            slot,
+            ..Default::default()
+        })
+    }
+
+    fn flushed_slots(flushed: Vec<EnvEvent>) -> Vec<(String, u64)> {
+        flushed
+            .into_iter()
+            .map(|(env, event)| match event {
+                BufferedEvent::Account(ev) => (env, ev.slot),
+                BufferedEvent::Transaction(ev) => (env, ev.slot),
+                BufferedEvent::AccountCreated(ev) => (env, ev.slot),
+            })
+            .collect()
+    }
+
+    fn env_slots(env: &str, slots: &[u64]) -> Vec<(String, u64)> {
+        slots.iter().map(|slot| (env.to_string(), *slot)).collect()
+    }
+
+    //      1
+    //     / \
+    //    2   3
+    //    |   |
+    //    4   5
+    fn forked_tree() -> SlotTree {
+        let mut tree = SlotTree::default();
+        tree.insert(2, 1);
+        tree.insert(3, 1);
+        tree.insert(4, 2);
+        tree.insert(5, 3);
+        tree
+    }
+
+    #[test]
+    fn processed_events_are_not_buffered() {
+        let tree = forked_tree();
+        let buffer = CommitmentBuffer::default();
+        buffer.push(4, Commitment::Processed, "dev", account_event(4));
+
+        assert!(buffer.flush(4, Commitment::Rooted, &tree).is_empty());
+    }
+
+    #[test]
+    fn flush_confirmed_keeps_other_forks() {
+        let tree = forked_tree();
+        let buffer = CommitmentBuffer::default();
+        for slot in 1..=5 {
+            buffer.push(slot, Commitment::Confirmed, "dev", account_event(slot));
+        }
+
+        assert_eq!(
+            flushed_slots(buffer.flush(4, Commitment::Confirmed, &tree)),
+            env_slots("dev", &[1, 2, 4])
+        );
+        // Already flushed ancestors are not published again
+        assert_eq!(
+            flushed_slots(buffer.flush(5, Commitment::Confirmed, &tree)),
+            env_slots("dev", &[3, 5])
+        );
+    }
+
+    #[test]
+    fn flush_rooted_discards_abandoned_forks() {
+        let tree = forked_tree();
+        let buffer = CommitmentBuffer::default();
+        for slot in 1..=5 {
+            buffer.push(slot, Commitment::Confirmed, "dev", account_event(slot));
+            buffer.push(slot, Commitment::Rooted, "stage", account_event(slot));
+        }
+
+        // Confirming a slot does not flush events waiting for it to be rooted
+        assert_eq!(
+            flushed_slots(buffer.flush(2, Commitment::Confirmed, &tree)),
+            env_slots("dev", &[1, 2])
+        );
+        // Rooting a slot implies it is confirmed
+        let mut expected = env_slots("dev", &[4]);
+        expected.extend(env_slots("stage", &[1, 2, 4]));
+        assert_eq!(
+            flushed_slots(buffer.flush(4, Commitment::Rooted, &tree)),
+            expected
+        );
+        // 3 was discarded since it is not an ancestor of 4, while 5 is still buffered since
+        // it is newer than the root
+        assert_eq!(
+            flushed_slots(buffer.flush(5, Commitment::Confirmed, &tree)),
+            env_slots("dev", &[5])
+        );
+    }
+
+    #[test]
+    fn discard_dead_slots() {
+        let tree = forked_tree();
+        let buffer = CommitmentBuffer::default();
+        for slot in 4..=5 {
+            buffer.push(slot, Commitment::Confirmed, "dev", account_event(slot));
+            buffer.push(slot, Commitment::Rooted, "stage", account_event(slot));
+        }
+
+        buffer.discard(&[5]);
+        let mut expected = env_slots("dev", &[4]);
+        expected.extend(env_slots("stage", &[4]));
+        assert_eq!(
+            flushed_slots(buffer.flush(4, Commitment::Rooted, &tree)),
+            expected
+        );
+        assert!(buffer.flush(5, Commitment::Confirmed, &tree).is_empty());
+    }
+
+    #[test]
+    fn discard_env() {
+        let tree = forked_tree();
+        let buffer = CommitmentBuffer::default();
+        for slot in 4..=5 {
+            buffer.push(slot, Commitment::Confirmed, "dev", account_event(slot));
+            buffer.push(slot, Commitment::Rooted, "stage", account_event(slot));
+        }
+        buffer.push(2, Commitment::Confirmed, "stage", account_event(2));
+
+        assert_eq!(buffer.discard_env("stage"), 3);
+        assert_eq!(
+            flushed_slots(buffer.flush(4, Commitment::Rooted, &tree)),
+            env_slots("dev", &[4])
+        );
+    }
+
+    #[test]
+    fn flush_rooted_publishes_slots_with_unknown_ancestry() {
+        let mut tree = SlotTree::default();
+        tree.insert(4, 3);
+        let buffer = CommitmentBuffer::default();
+        buffer.push(1, Commitment::Rooted, "dev", account_event(1));
+        buffer.push(4, Commitment::Rooted, "dev", account_event(4));
+
+        assert_eq!(
+            flushed_slots(buffer.flush(4, Commitment::Rooted, &tree)),
+            env_slots("dev", &[1, 4])
+        );
+    }
+}
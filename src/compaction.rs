@@ -0,0 +1,177 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Mutex,
+};
+
+use crate::{prom::ACCOUNT_UPDATES_COALESCED_TOTAL, UpdateAccountEvent};
+
+type Slots = BTreeMap<u64, HashMap<Vec<u8>, UpdateAccountEvent>>;
+
+/// Holds the account updates of each slot per environment, keeping only the update with the
+/// highest write_version per account, until the slot advances or its status is updated.
+#[derive(Default)]
+pub struct AccountCompactor {
+    envs: Mutex<HashMap<String, Slots>>,
+}
+
+impl AccountCompactor {
+    /// Adds [event] to its slot for [env], coalescing it with a previous update of the same
+    /// account. Returns the surviving updates of all slots of [env] older than the slot of
+    /// [event] since those slots advanced.
+    pub fn push(&self, env: &str, event: UpdateAccountEvent) -> Vec<UpdateAccountEvent> {
+        let mut envs = self.envs.lock().expect("compaction mutex poisoned");
+        if !envs.contains_key(env) {
+            envs.insert(env.to_string(), Slots::default());
+        }
+        let slots = envs.get_mut(env).expect("env was just inserted");
+        let slot = event.slot;
+        let accounts = slots.entry(slot).or_default();
+        let coalesced = match accounts.get(&event.pubkey) {
+            Some(existing) if existing.write_version > event.write_version => true,
+            existing => {
+                let coalesced = existing.is_some();
+                accounts.insert(event.pubkey.clone(), event);
+                coalesced
+            }
+        };
+        if coalesced {
+            ACCOUNT_UPDATES_COALESCED_TOTAL.inc();
+        }
+
+        let advanced = slots.range(..slot).map(|(s, _)| *s).collect::<Vec<_>>();
+        Self::take(slots, advanced)
+    }
+
+    /// Removes and returns the surviving updates of [slot] and all older slots of every
+    /// environment, in slot and write_version order per environment.
+    pub fn flush(&self, slot: u64) -> Vec<(String, UpdateAccountEvent)> {
+        let mut envs = self.envs.lock().expect("compaction mutex poisoned");
+        envs.retain(|_, slots| !slots.is_empty());
+        envs.iter_mut()
+            .flat_map(|(env, slots)| {
+                let flushed = slots.range(..=slot).map(|(s, _)| *s).collect::<Vec<_>>();
+                Self::take(slots, flushed)
+                    .into_iter()
+                    .map(|event| (env.clone(), event))
+            })
+            .collect()
+    }
+
+    /// Discards the updates held for [env] and returns how many were discarded.
+    pub fn discard_env(&self, env: &str) -> usize {
+        self.envs
+            .lock()
+            .expect("compaction mutex poisoned")
+            .remove(env)
+            .map_or(0, |slots| slots.values().map(HashMap::len).sum())
+    }
+
+    fn take(slots: &mut Slots, to_take: Vec<u64>) -> Vec<UpdateAccountEvent> {
+        let mut events = vec![];
+        for slot in to_take {
+            let mut accounts = slots
+                .remove(&slot)
+                .unwrap_or_default()
+                .into_values()
+                .collect::<Vec<_>>();
+            accounts.sort_by_key(|event| event.write_version);
+            events.extend(accounts);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_event(slot: u64, pubkey: u8, write_version: u64) -> UpdateAccountEvent {
+        UpdateAccountEvent {
+            slot,
+            pubkey: vec![pubkey; 32],
+            write_version,
+            ..Default::default()
+        }
+    }
+
+    fn write_versions(events: Vec<UpdateAccountEvent>) -> Vec<(u64, u64)> {
+        events
+            .into_iter()
+            .map(|event| (event.slot, event.write_version))
+            .collect()
+    }
+
+    fn flushed(compactor: &AccountCompactor, slot: u64) -> Vec<(String, u64, u64)> {
+        let mut events = compactor
+            .flush(slot)
+            .into_iter()
+            .map(|(env, event)| (env, event.slot, event.write_version))
+            .collect::<Vec<_>>();
+        events.sort_by(|a, b| a.0.cmp(&b.0));
+        events
+    }
+
+    #[test]
+    fn keeps_highest_write_version_per_account() {
+        let compactor = AccountCompactor::default();
+        assert!(compactor.push("dev", account_event(1, 1, 2)).is_empty());
+        assert!(compactor.push("dev", account_event(1, 2, 3)).is_empty());
+        assert!(compactor.push("dev", account_event(1, 1, 4)).is_empty());
+        // Older updates arriving late do not replace newer ones
+        assert!(compactor.push("dev", account_event(1, 1, 1)).is_empty());
+
+        assert_eq!(
+            flushed(&compactor, 1),
+            vec![("dev".to_string(), 1, 3), ("dev".to_string(), 1, 4)]
+        );
+        assert!(compactor.flush(1).is_empty());
+    }
+
+    #[test]
+    fn flushes_older_slots_once_slot_advances() {
+        let compactor = AccountCompactor::default();
+        assert!(compactor.push("dev", account_event(1, 1, 1)).is_empty());
+        assert_eq!(
+            write_versions(compactor.push("dev", account_event(2, 1, 2))),
+            vec![(1, 1)]
+        );
+        assert!(compactor.push("dev", account_event(2, 2, 3)).is_empty());
+        assert_eq!(
+            write_versions(compactor.push("dev", account_event(3, 1, 4))),
+            vec![(2, 2), (2, 3)]
+        );
+        assert_eq!(flushed(&compactor, 3), vec![("dev".to_string(), 3, 4)]);
+    }
+
+    #[test]
+    fn compacts_per_env() {
+        let compactor = AccountCompactor::default();
+        assert!(compactor.push("dev", account_event(1, 1, 2)).is_empty());
+        assert!(compactor.push("prod", account_event(1, 1, 1)).is_empty());
+        // Only the slots of the same environment advance
+        assert_eq!(
+            write_versions(compactor.push("dev", account_event(2, 1, 3))),
+            vec![(1, 2)]
+        );
+        assert_eq!(
+            flushed(&compactor, 2),
+            vec![("dev".to_string(), 2, 3), ("prod".to_string(), 1, 1)]
+        );
+    }
+
+    #[test]
+    fn discard_env() {
+        let compactor = AccountCompactor::default();
+        assert!(compactor.push("dev", account_event(1, 1, 1)).is_empty());
+        assert!(compactor.push("prod", account_event(1, 1, 2)).is_empty());
+        assert_eq!(
+            write_versions(compactor.push("prod", account_event(2, 2, 3))),
+            vec![(1, 2)]
+        );
+        assert!(compactor.push("prod", account_event(2, 3, 4)).is_empty());
+
+        assert_eq!(compactor.discard_env("prod"), 2);
+        assert_eq!(compactor.discard_env("stage"), 0);
+        assert_eq!(flushed(&compactor, 2), vec![("dev".to_string(), 1, 1)]);
+    }
+}
@@ -16,18 +16,25 @@ use std::str::FromStr;
 
 use solana_program::pubkey::Pubkey;
 
-use crate::{Cluster, EnvConfig};
+use crate::{
+    interpolation::interpolate,
+    unknown_keys::{check_keys, KeyWarnings},
+    Cluster, EnvConfig,
+};
 
 use {
     crate::PrometheusService,
+    log::{error, warn},
     rdkafka::producer::{DefaultProducerContext, ThreadedProducer},
-    serde::Deserialize,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
         GeyserPluginError, Result as PluginResult,
     },
     std::{
         collections::{HashMap, HashSet},
-        fs::File,
+        ffi::OsStr,
+        fs::{self, File},
         io::Result as IoResult,
         net::SocketAddr,
         path::Path,
@@ -35,7 +42,7 @@ use {
 };
 
 /// Plugin config.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Config {
     #[serde(default = "Cluster::default")]
     pub cluster: Cluster,
@@ -45,6 +52,8 @@ pub struct Config {
     #[serde(default)]
     pub shutdown_timeout_ms: u64,
     /// Kafka topic to send account updates to. Omit to disable.
+    /// Kafka topics may contain `{cluster}` and `{event}` placeholders, account topics also
+    /// `{owner}` and `{owner_label}`, see [TopicTemplate].
     #[serde(default)]
     pub update_account_topic: String,
     /// Kafka topic overrides to send specific account updates to. Omit to disable.
@@ -65,27 +74,68 @@ pub struct Config {
     /// ```
     #[serde(default)]
     pub update_account_topic_overrides: HashMap<String, HashSet<String>>,
+    /// Labels of owner programs by their address that are expanded in place of `{owner_label}`
+    /// in topic templates. Owners without a label are expanded as their address.
+    #[serde(default)]
+    pub owner_labels: HashMap<String, String>,
 
     /// Kafka topic to send slot status updates to. Omit to disable.
-    #[serde(default)]
+    #[serde(default, alias = "update_slot_topic")]
     pub slot_status_topic: String,
     /// Kafka topic to send transaction updates to. Omit to disable.
-    #[serde(default)]
+    #[serde(default, alias = "update_transaction_topic")]
     pub transaction_topic: String,
+    /// Kafka topic to send block metadata to. Omit to disable.
+    #[serde(default)]
+    pub block_metadata_topic: String,
+    /// Kafka topic to send entries to. Omit to disable.
+    #[serde(default)]
+    pub entry_topic: String,
+    /// Kafka topic to send the end of startup marker to once all accounts were published on
+    /// startup. Only used together with [publish_all_accounts]. Omit to disable.
+    #[serde(default)]
+    pub end_of_startup_topic: String,
     /// Publish all accounts on startup. Omit to disable.
     #[serde(default)]
     pub publish_all_accounts: bool,
+    /// Kafka topic to send the accounts published on startup to instead of
+    /// [update_account_topic]. Omit to use [update_account_topic].
+    #[serde(default)]
+    pub startup_account_topic: String,
+    /// Maximum number of accounts published per second on startup. Omit to not limit.
+    #[serde(default)]
+    pub startup_max_messages_per_second: u64,
+    /// Maximum number of bytes published per second on startup. Omit to not limit.
+    #[serde(default)]
+    pub startup_max_bytes_per_second: u64,
     /// Detects account deletions from transactions where lamports went to `0`
     /// for an account and publishes them for each other account, that is part
     /// of that transaction (assuming it could be the owner of the deleted account).
     /// NOTE that this will enable transaction notifications. Omit to disable.
     #[serde(default)]
     pub publish_account_deletions: bool,
+    /// Number of accounts whose owner is remembered in order to publish account deletions
+    /// with the actual owner of the deleted account. Deletions of accounts that are not cached
+    /// are published for each other account of the transaction. Set to `0` to disable.
+    #[serde(default = "Config::default_owner_cache_capacity")]
+    pub owner_cache_capacity: usize,
+    /// Kafka topic to send accounts created in a transaction to, i.e. accounts whose lamports
+    /// went from `0` to a positive balance. Creations are published with the owner if it is
+    /// cached, see [owner_cache_capacity].
+    /// NOTE that this will enable transaction notifications. Omit to disable.
+    #[serde(default)]
+    pub account_created_topic: String,
     /// Publishes account updates even if the txn_signature is not present.
     /// This will include account updates that occur without a corresponding
     /// transaction, i.e. caused by validator book-keeping. Omit to disable.
     #[serde(default)]
     pub publish_accounts_without_signature: bool,
+    /// Publishes only the update with the highest write_version per account within a slot.
+    /// The surviving updates of a slot are published once the slot advances or its status is
+    /// updated. Accounts published on startup are not compacted. Environments may override it.
+    /// Omit to disable.
+    #[serde(default)]
+    pub compact_account_updates: bool,
     /// Wrap all messages in a unified wrapper object. Omit to disable.
     #[serde(default)]
     pub wrap_messages: bool,
@@ -95,6 +145,15 @@ pub struct Config {
     /// Prometheus endpoint.
     #[serde(default)]
     pub prometheus: Option<SocketAddr>,
+    /// Interval in seconds in which the config file is checked for changes. Changed
+    /// environments, topics, allowlists and filters are applied without restarting the
+    /// validator. Omit to disable.
+    #[serde(default)]
+    pub reload_interval_secs: u64,
+    /// Reject unknown keys in the config and its environments instead of logging a warning for
+    /// each of them. Omit to only warn.
+    #[serde(default)]
+    pub strict_config: bool,
 }
 
 impl Default for Config {
@@ -104,46 +163,130 @@ impl Default for Config {
             shutdown_timeout_ms: 30_000,
             update_account_topic: Default::default(),
             update_account_topic_overrides: Default::default(),
+            owner_labels: Default::default(),
             slot_status_topic: Default::default(),
             transaction_topic: Default::default(),
+            block_metadata_topic: Default::default(),
+            entry_topic: Default::default(),
+            end_of_startup_topic: Default::default(),
             publish_all_accounts: Default::default(),
+            startup_account_topic: Default::default(),
+            startup_max_messages_per_second: Default::default(),
+            startup_max_bytes_per_second: Default::default(),
             publish_account_deletions: Default::default(),
+            owner_cache_capacity: Self::default_owner_cache_capacity(),
+            account_created_topic: Default::default(),
             publish_accounts_without_signature: Default::default(),
+            compact_account_updates: Default::default(),
             wrap_messages: Default::default(),
             environments: Default::default(),
             prometheus: None,
+            reload_interval_secs: Default::default(),
+            strict_config: Default::default(),
         }
     }
 }
 
 impl Config {
-    /// Read plugin from JSON file.
+    fn default_owner_cache_capacity() -> usize {
+        100_000
+    }
+
+    /// Read plugin from JSON, YAML or TOML file, see [Config::parse].
+    /// `${VAR}` and `${file:/path}` references in string values are resolved, see [interpolate].
+    /// Unknown and deprecated keys are logged as warnings, see [Config::read_with_warnings].
     pub fn read_from<P: AsRef<Path>>(config_path: P) -> PluginResult<Self> {
-        let file = File::open(config_path)?;
-        let mut this: Self = serde_json::from_reader(file)
+        let (this, warnings) = Self::read_with_warnings(config_path)?;
+        for warning in warnings {
+            warn!("{warning}");
+        }
+        Ok(this)
+    }
+
+    /// Read plugin from JSON, YAML or TOML file like [Config::read_from] and return it together
+    /// with the unknown keys that are ignored and the deprecated keys that are used.
+    /// Fails on unknown keys instead if [strict_config] is set.
+    pub fn read_with_warnings<P: AsRef<Path>>(config_path: P) -> PluginResult<(Self, Vec<String>)> {
+        let mut value = Self::parse(config_path.as_ref())?;
+        interpolate(&mut value).map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })?;
+        let KeyWarnings {
+            unknown,
+            deprecated,
+        } = check_keys(&value);
+        let mut this: Self = serde_path_to_error::deserialize(value)
             .map_err(|e| GeyserPluginError::ConfigFileReadError { msg: e.to_string() })?;
+        if this.strict_config && !unknown.is_empty() {
+            return Err(GeyserPluginError::ConfigFileReadError {
+                msg: unknown.join(" | "),
+            });
+        }
         for env_config in this.environments.iter_mut() {
             if let EnvConfig::Kafka(env_config) = env_config {
                 env_config.fill_defaults();
             }
         }
-        Ok(this)
+        Ok((this, unknown.into_iter().chain(deprecated).collect()))
+    }
+
+    /// Parses the config file as YAML or TOML if it has the respective extension and as JSON
+    /// otherwise.
+    fn parse(config_path: &Path) -> PluginResult<Value> {
+        let value = match config_path.extension().and_then(OsStr::to_str) {
+            Some("yaml" | "yml") => {
+                serde_yaml::from_reader(File::open(config_path)?).map_err(|e| e.to_string())
+            }
+            Some("toml") => {
+                toml::from_str(&fs::read_to_string(config_path)?).map_err(|e| e.to_string())
+            }
+            _ => serde_json::from_reader(File::open(config_path)?).map_err(|e| e.to_string()),
+        };
+        value.map_err(|msg| GeyserPluginError::ConfigFileReadError { msg })
     }
 
     pub fn update_topic_overrides_by_account(&self) -> HashMap<Vec<u8>, String> {
+        Self::topic_overrides_by_account(&self.update_account_topic_overrides)
+    }
+
+    pub(crate) fn topic_overrides_by_account(
+        overrides: &HashMap<String, HashSet<String>>,
+    ) -> HashMap<Vec<u8>, String> {
         let mut map = HashMap::new();
-        for (topic, accounts) in &self.update_account_topic_overrides {
+        for (topic, accounts) in overrides {
             for address in accounts {
-                let pubkey = Pubkey::from_str(address)
-                    .unwrap_or_else(|_| panic!("Invalid pubkey {address}"))
-                    .to_bytes()
-                    .to_vec();
-                map.insert(pubkey, topic.clone());
+                // Invalid pubkeys are rejected by [Config::validate] already
+                match Pubkey::from_str(address) {
+                    Ok(pubkey) => {
+                        map.insert(pubkey.to_bytes().to_vec(), topic.clone());
+                    }
+                    Err(err) => error!("Ignoring topic override for {address}: {err}"),
+                }
+            }
+        }
+        map
+    }
+
+    pub(crate) fn owner_labels_by_account(&self) -> HashMap<Vec<u8>, String> {
+        let mut map = HashMap::new();
+        for (address, label) in &self.owner_labels {
+            // Invalid pubkeys are rejected by [Config::validate] already
+            match Pubkey::from_str(address) {
+                Ok(pubkey) => {
+                    map.insert(pubkey.to_bytes().to_vec(), label.clone());
+                }
+                Err(err) => error!("Ignoring owner label for {address}: {err}"),
             }
         }
         map
     }
 
+    /// Returns the config as JSON with the values of secrets, i.e. passwords and the allowlist
+    /// authorization header, replaced.
+    pub fn to_redacted_json(&self) -> serde_json::Result<Value> {
+        let mut value = serde_json::to_value(self)?;
+        redact_secrets(&mut value);
+        Ok(value)
+    }
+
     pub fn create_prometheus(&self) -> IoResult<Option<PrometheusService>> {
         self.prometheus.map(PrometheusService::new).transpose()
     }
@@ -151,8 +294,31 @@ impl Config {
 
 pub type Producer = ThreadedProducer<DefaultProducerContext>;
 
+pub(crate) fn is_secret(key: &str) -> bool {
+    key.contains("password") || key.contains("secret") || key == "program_allowlist_auth"
+}
+
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(secret) if is_secret(key) && !secret.is_empty() => {
+                        *value = Value::String("<redacted>".to_string())
+                    }
+                    value => redact_secrets(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::{env_config::EnvTopics, EventPolicy};
+
     use super::*;
 
     #[test]
@@ -198,4 +364,119 @@ mod tests {
             expected_overrides_by_account
         );
     }
+
+    fn overrides_by_account(topic: &str, account: &str) -> HashMap<Vec<u8>, String> {
+        let mut map = HashMap::new();
+        map.insert(
+            Pubkey::from_str(account).unwrap().to_bytes().to_vec(),
+            topic.to_string(),
+        );
+        map
+    }
+
+    #[test]
+    fn multi_env_topic_overrides() {
+        let config = Config::read_from("test/fixtures/configs/multi-env-topic-overrides.json")
+            .expect("should deserialize config");
+        assert_eq!(config.environments.len(), 3);
+
+        // Topics configured per environment take precedence, including empty ones
+        assert_eq!(
+            config.environments[0].topics(&config),
+            EnvTopics {
+                update_account_topic: "dev.account_update".to_string(),
+                update_account_topic_overrides: overrides_by_account(
+                    "dev.spl.account_update",
+                    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb"
+                ),
+                slot_status_topic: "".to_string(),
+                transaction_topic: "geyser.mainnet.transaction".to_string(),
+                wrap_messages: true,
+            }
+        );
+
+        // Omitted topics fall back to the global ones
+        assert_eq!(
+            config.environments[1].topics(&config),
+            EnvTopics {
+                update_account_topic: "geyser.mainnet.account_update".to_string(),
+                update_account_topic_overrides: config.update_topic_overrides_by_account(),
+                slot_status_topic: "geyser.mainnet.slot_status".to_string(),
+                transaction_topic: "geyser.mainnet.transaction".to_string(),
+                wrap_messages: false,
+            }
+        );
+
+        assert!(matches!(config.environments[2], EnvConfig::Local(_)));
+        assert_eq!(
+            config.environments[2].topics(&config),
+            EnvTopics {
+                update_account_topic: "geyser.mainnet.account_update".to_string(),
+                update_account_topic_overrides: overrides_by_account(
+                    "geyser.mainnet.spl.account_update",
+                    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+                ),
+                slot_status_topic: "geyser.mainnet.slot_status".to_string(),
+                transaction_topic: "transactions".to_string(),
+                wrap_messages: true,
+            }
+        );
+    }
+
+    #[test]
+    fn multi_env_event_policies() {
+        let config = Config::read_from("test/fixtures/configs/multi-env-event-policies.json")
+            .expect("should deserialize config");
+        assert_eq!(
+            config.environments[0].policy(&config),
+            EventPolicy {
+                publish_all_accounts: true,
+                publish_account_deletions: true,
+                publish_accounts_without_signature: false,
+                compact_account_updates: true,
+            }
+        );
+        assert_eq!(
+            config.environments[1].policy(&config),
+            EventPolicy {
+                publish_all_accounts: false,
+                publish_account_deletions: true,
+                publish_accounts_without_signature: true,
+                compact_account_updates: false,
+            }
+        );
+    }
+
+    #[test]
+    fn yaml_and_toml_configs() {
+        let expected = Config::read_from("test/fixtures/configs/multi-env-typed.json")
+            .expect("should deserialize config")
+            .to_redacted_json()
+            .unwrap();
+        assert_eq!(
+            expected["environments"][0]["kafka"]["compression.type"],
+            "lz4"
+        );
+        for path in [
+            "test/fixtures/configs/multi-env-typed.yaml",
+            "test/fixtures/configs/multi-env-typed.toml",
+        ] {
+            let (config, warnings) =
+                Config::read_with_warnings(path).expect("should deserialize config");
+            assert!(warnings.is_empty());
+            assert_eq!(config.to_redacted_json().unwrap(), expected, "{path}");
+        }
+    }
+
+    #[test]
+    fn redacts_secrets() {
+        let config = Config::read_from("test/fixtures/configs/single-env-topic-overrides.json")
+            .expect("should deserialize config");
+        let json = config.to_redacted_json().unwrap();
+        let env = &json["environments"][0];
+        assert_eq!(env["kafka"]["sasl.password"], "<redacted>");
+        assert_eq!(env["kafka"]["sasl.username"], "sasl.user");
+        assert_eq!(env["program_allowlist_auth"], "<redacted>");
+        assert_eq!(env["program_allowlist_slot_interval"], 150);
+    }
 }
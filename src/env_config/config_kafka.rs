@@ -1,15 +1,19 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use rdkafka::{
     config::FromClientConfigAndContext, error::KafkaResult, producer::ThreadedProducer,
     ClientConfig,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::prom::StatsThreadedProducerContext;
+use crate::{
+    prom::StatsThreadedProducerContext, Commitment, Config, OverflowPolicy, TransactionMatch,
+};
+
+use super::EnvTopics;
 
 /// Environment specific config.
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct EnvConfigKafka {
     /// Name of the environment
     #[serde(default)]
@@ -19,7 +23,8 @@ pub struct EnvConfigKafka {
     pub kafka: HashMap<String, String>,
 
     /// Allowlist of programs to publish.
-    /// If empty, no accounts are published.
+    /// If empty, no accounts are published unless a [program_denylist] is configured, in which
+    /// case all accounts not owned by programs in the denylist are published.
     /// If not empty, only accounts owned by programs in this list are published.
     #[serde(default)]
     pub program_allowlist: Vec<String>,
@@ -47,6 +52,106 @@ pub struct EnvConfigKafka {
     /// Slots interval which determines how many slots to wait before updating the allowlist.
     #[serde(default)]
     pub program_allowlist_slot_interval: u64,
+
+    /// Denylist of programs to never publish, which takes precedence over the
+    /// [program_allowlist].
+    #[serde(default)]
+    pub program_denylist: Vec<String>,
+
+    /// URL to fetch denylist updates from, using the same schema as the
+    /// [program_allowlist_url]. It is requested with the [program_allowlist_auth] header and
+    /// updated every [program_allowlist_slot_interval] slots.
+    #[serde(default)]
+    pub program_denylist_url: String,
+
+    /// Kafka topic to send account updates to instead of the global [update_account_topic].
+    /// Omit to use the global one.
+    #[serde(default)]
+    pub update_account_topic: Option<String>,
+
+    /// Account update topic overrides by program used instead of the global
+    /// [update_account_topic_overrides]. Omit to use the global ones.
+    #[serde(default)]
+    pub update_account_topic_overrides: Option<HashMap<String, HashSet<String>>>,
+
+    /// Kafka topic to send slot status updates to instead of the global [slot_status_topic].
+    /// Omit to use the global one.
+    #[serde(default, alias = "update_slot_topic")]
+    pub slot_status_topic: Option<String>,
+
+    /// Kafka topic to send transaction updates to instead of the global [transaction_topic].
+    /// Omit to use the global one.
+    #[serde(default, alias = "update_transaction_topic")]
+    pub transaction_topic: Option<String>,
+
+    /// Wrap all messages in a unified wrapper object instead of using the global
+    /// [wrap_messages]. Omit to use the global one.
+    #[serde(default)]
+    pub wrap_messages: Option<bool>,
+
+    /// Commitment level a slot needs to reach before its account and transaction updates are
+    /// published to this environment, one of `processed`, `confirmed` or `rooted`.
+    /// Updates of slots that never reach it are dropped.
+    #[serde(default)]
+    pub commitment: Commitment,
+
+    /// Which account keys of a transaction are matched against the [program_allowlist], one of
+    /// `any_account`, `program_ids` or `writable_accounts`.
+    #[serde(default)]
+    pub transaction_match: TransactionMatch,
+
+    /// Exclude simple vote transactions from the transactions published to this environment.
+    #[serde(default)]
+    pub exclude_vote_transactions: bool,
+
+    /// Kafka topic to send vote transactions to instead of [transaction_topic]. Omit to send
+    /// them to [transaction_topic].
+    #[serde(default)]
+    pub vote_transaction_topic: String,
+
+    /// Exclude failed transactions from the transactions published to this environment.
+    #[serde(default)]
+    pub exclude_failed_transactions: bool,
+
+    /// Kafka topic to send failed transactions to instead of [transaction_topic]. Omit to send
+    /// them to [transaction_topic].
+    #[serde(default)]
+    pub failed_transaction_topic: String,
+
+    /// Publish all accounts on startup to this environment instead of using the global
+    /// [publish_all_accounts]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_all_accounts: Option<bool>,
+
+    /// Publish account deletions detected from transactions to this environment instead of
+    /// using the global [publish_account_deletions]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_account_deletions: Option<bool>,
+
+    /// Publish account updates without a txn_signature to this environment instead of using the
+    /// global [publish_accounts_without_signature]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_accounts_without_signature: Option<bool>,
+
+    /// Compact account updates published to this environment instead of using the global
+    /// [compact_account_updates]. Omit to use the global one.
+    #[serde(default)]
+    pub compact_account_updates: Option<bool>,
+
+    /// Maximum number of events queued for publishing to this environment. If not `0`, events
+    /// are published by [queue_workers] threads instead of the validator callbacks.
+    #[serde(default)]
+    pub queue_capacity: usize,
+
+    /// Number of threads publishing queued events, defaults to one. Events may be published out
+    /// of order when using more than one.
+    #[serde(default)]
+    pub queue_workers: usize,
+
+    /// What to do with events while the queue is full, one of `block`, `drop_oldest` or
+    /// `drop_newest`. Startup accounts always block.
+    #[serde(default)]
+    pub queue_overflow_policy: OverflowPolicy,
 }
 
 impl Default for EnvConfigKafka {
@@ -57,12 +162,36 @@ impl Default for EnvConfigKafka {
             program_allowlist: Default::default(),
             program_allowlist_url: Default::default(),
             program_allowlist_auth: Default::default(),
-            program_allowlist_slot_interval: 150, // roughly 60 secs
+            program_allowlist_slot_interval: Self::default_program_allowlist_slot_interval(),
+            program_denylist: Default::default(),
+            program_denylist_url: Default::default(),
+            update_account_topic: Default::default(),
+            update_account_topic_overrides: Default::default(),
+            slot_status_topic: Default::default(),
+            transaction_topic: Default::default(),
+            wrap_messages: Default::default(),
+            commitment: Default::default(),
+            transaction_match: Default::default(),
+            exclude_vote_transactions: Default::default(),
+            vote_transaction_topic: Default::default(),
+            exclude_failed_transactions: Default::default(),
+            failed_transaction_topic: Default::default(),
+            publish_all_accounts: Default::default(),
+            publish_account_deletions: Default::default(),
+            publish_accounts_without_signature: Default::default(),
+            compact_account_updates: Default::default(),
+            queue_capacity: Default::default(),
+            queue_workers: Default::default(),
+            queue_overflow_policy: Default::default(),
         }
     }
 }
 
 impl EnvConfigKafka {
+    pub(crate) fn default_program_allowlist_slot_interval() -> u64 {
+        150 // roughly 60 secs
+    }
+
     /// Create rdkafka::FutureProducer from config.
     pub fn producer(&self) -> KafkaResult<ThreadedProducer<StatsThreadedProducerContext>> {
         let mut config = ClientConfig::new();
@@ -72,6 +201,33 @@ impl EnvConfigKafka {
         ThreadedProducer::from_config_and_context(&config, StatsThreadedProducerContext::default())
     }
 
+    /// Returns the topics of this environment, falling back to the global ones of [config].
+    pub fn topics(&self, config: &Config) -> EnvTopics {
+        EnvTopics {
+            update_account_topic: self
+                .update_account_topic
+                .as_ref()
+                .unwrap_or(&config.update_account_topic)
+                .clone(),
+            update_account_topic_overrides: Config::topic_overrides_by_account(
+                self.update_account_topic_overrides
+                    .as_ref()
+                    .unwrap_or(&config.update_account_topic_overrides),
+            ),
+            slot_status_topic: self
+                .slot_status_topic
+                .as_ref()
+                .unwrap_or(&config.slot_status_topic)
+                .clone(),
+            transaction_topic: self
+                .transaction_topic
+                .as_ref()
+                .unwrap_or(&config.transaction_topic)
+                .clone(),
+            wrap_messages: self.wrap_messages.unwrap_or(config.wrap_messages),
+        }
+    }
+
     fn set_default(&mut self, k: &'static str, v: &'static str) {
         if !self.kafka.contains_key(k) {
             self.kafka.insert(k.to_owned(), v.to_owned());
@@ -1,7 +1,13 @@
-use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Commitment, Config, OverflowPolicy, TransactionMatch};
+
+use super::EnvTopics;
 
 /// Environment specific config for local development.
-#[derive(Deserialize, Default)]
+#[derive(Deserialize, Serialize, Default)]
 pub struct EnvConfigLocal {
     /// Name of the environment
     #[serde(default)]
@@ -14,6 +20,16 @@ pub struct EnvConfigLocal {
     #[serde(default)]
     pub program_allowlist: Vec<String>,
 
+    /// Denylist of programs to never publish, which takes precedence over the
+    /// [program_allowlist].
+    #[serde(default)]
+    pub program_denylist: Vec<String>,
+
+    /// URL to fetch denylist updates from, using the same schema as the `program_allowlist_url`
+    /// of Kafka environments. It is updated every 150 slots.
+    #[serde(default)]
+    pub program_denylist_url: String,
+
     /// URL to publish to.
     pub url: String,
 
@@ -25,4 +41,121 @@ pub struct EnvConfigLocal {
     /// - Config Program: Config1111111111111111111111111111111111111
     #[serde(default)]
     pub include_system_accounts: bool,
+
+    /// Path to send account updates to instead of the global [update_account_topic].
+    /// Omit to use the global one.
+    #[serde(default)]
+    pub update_account_topic: Option<String>,
+
+    /// Account update topic overrides by program used instead of the global
+    /// [update_account_topic_overrides]. Omit to use the global ones.
+    #[serde(default)]
+    pub update_account_topic_overrides: Option<HashMap<String, HashSet<String>>>,
+
+    /// Path to send slot status updates to instead of the global [slot_status_topic].
+    /// Omit to use the global one.
+    #[serde(default, alias = "update_slot_topic")]
+    pub slot_status_topic: Option<String>,
+
+    /// Path to send transaction updates to instead of the global [transaction_topic].
+    /// Omit to use the global one.
+    #[serde(default, alias = "update_transaction_topic")]
+    pub transaction_topic: Option<String>,
+
+    /// Wrap all messages in a unified wrapper object instead of using the global
+    /// [wrap_messages]. Omit to use the global one.
+    #[serde(default)]
+    pub wrap_messages: Option<bool>,
+
+    /// Commitment level a slot needs to reach before its account and transaction updates are
+    /// published to this environment, one of `processed`, `confirmed` or `rooted`.
+    #[serde(default)]
+    pub commitment: Commitment,
+
+    /// Which account keys of a transaction are matched against the [program_allowlist], one of
+    /// `any_account`, `program_ids` or `writable_accounts`.
+    #[serde(default)]
+    pub transaction_match: TransactionMatch,
+
+    /// Exclude simple vote transactions from the transactions published to this environment.
+    #[serde(default)]
+    pub exclude_vote_transactions: bool,
+
+    /// Path to send vote transactions to instead of [transaction_topic]. Omit to send them to
+    /// [transaction_topic].
+    #[serde(default)]
+    pub vote_transaction_topic: String,
+
+    /// Exclude failed transactions from the transactions published to this environment.
+    #[serde(default)]
+    pub exclude_failed_transactions: bool,
+
+    /// Path to send failed transactions to instead of [transaction_topic]. Omit to send them to
+    /// [transaction_topic].
+    #[serde(default)]
+    pub failed_transaction_topic: String,
+
+    /// Publish all accounts on startup to this environment instead of using the global
+    /// [publish_all_accounts]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_all_accounts: Option<bool>,
+
+    /// Publish account deletions detected from transactions to this environment instead of
+    /// using the global [publish_account_deletions]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_account_deletions: Option<bool>,
+
+    /// Publish account updates without a txn_signature to this environment instead of using the
+    /// global [publish_accounts_without_signature]. Omit to use the global one.
+    #[serde(default)]
+    pub publish_accounts_without_signature: Option<bool>,
+
+    /// Compact account updates published to this environment instead of using the global
+    /// [compact_account_updates]. Omit to use the global one.
+    #[serde(default)]
+    pub compact_account_updates: Option<bool>,
+
+    /// Maximum number of events queued for publishing to this environment. If not `0`, events
+    /// are published by [queue_workers] threads instead of the validator callbacks.
+    #[serde(default)]
+    pub queue_capacity: usize,
+
+    /// Number of threads publishing queued events, defaults to one. Events may be published out
+    /// of order when using more than one.
+    #[serde(default)]
+    pub queue_workers: usize,
+
+    /// What to do with events while the queue is full, one of `block`, `drop_oldest` or
+    /// `drop_newest`. Startup accounts always block.
+    #[serde(default)]
+    pub queue_overflow_policy: OverflowPolicy,
+}
+
+impl EnvConfigLocal {
+    /// Returns the paths of this environment, falling back to the global topics of [config].
+    pub fn topics(&self, config: &Config) -> EnvTopics {
+        EnvTopics {
+            update_account_topic: self
+                .update_account_topic
+                .as_ref()
+                .unwrap_or(&config.update_account_topic)
+                .clone(),
+            update_account_topic_overrides: Config::topic_overrides_by_account(
+                self.update_account_topic_overrides
+                    .as_ref()
+                    .unwrap_or(&config.update_account_topic_overrides),
+            ),
+            slot_status_topic: self
+                .slot_status_topic
+                .as_ref()
+                .unwrap_or(&config.slot_status_topic)
+                .clone(),
+            transaction_topic: self
+                .transaction_topic
+                .as_ref()
+                .unwrap_or(&config.transaction_topic)
+                .clone(),
+            wrap_messages: self.wrap_messages.unwrap_or(config.wrap_messages),
+        }
+    }
 }
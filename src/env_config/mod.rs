@@ -2,20 +2,281 @@ mod config_kafka;
 mod config_local;
 pub use config_kafka::EnvConfigKafka;
 pub use config_local::EnvConfigLocal;
-use serde::Deserialize;
+use std::collections::HashMap;
 
-#[derive(Deserialize)]
-#[serde(untagged)]
+use serde::{
+    de::{DeserializeOwned, Error},
+    Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
+
+use crate::{filter::EventPolicy, publisher::QueueConfig, Commitment, Config, TransactionMatch};
+
+/// Values of the `type` field of an environment.
+const ENV_TYPES: &[&str] = &["kafka", "local"];
+
+/// Topics of an environment, see [EnvConfigKafka::topics].
+#[derive(Debug, PartialEq, Eq)]
+pub struct EnvTopics {
+    pub update_account_topic: String,
+    pub update_account_topic_overrides: HashMap<Vec<u8>, String>,
+    pub slot_status_topic: String,
+    pub transaction_topic: String,
+    pub wrap_messages: bool,
+}
+
+/// Config of an environment, selected by its `type` field.
+/// Environments without a `type` are Kafka environments if they have a `kafka` config and
+/// local ones if they have a `url`.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum EnvConfig {
     Kafka(EnvConfigKafka),
     Local(EnvConfigLocal),
 }
 
+impl<'de> Deserialize<'de> for EnvConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = Map::deserialize(deserializer)?;
+        let name = match map.get("name").and_then(Value::as_str) {
+            Some(name) if !name.is_empty() => format!(" '{name}'"),
+            _ => String::new(),
+        };
+        let env_type = match map.remove("type") {
+            Some(Value::String(env_type)) => env_type,
+            Some(env_type) => {
+                return Err(D::Error::custom(format!(
+                    "Invalid type {env_type} of environment{name}, expected one of {}",
+                    ENV_TYPES.join(", ")
+                )))
+            }
+            None => match Self::untagged_type(&map) {
+                Some(env_type) => env_type.to_string(),
+                None => {
+                    return Err(D::Error::custom(format!(
+                        "Need to provide a type, a kafka config or a url for environment{name}"
+                    )))
+                }
+            },
+        };
+        let value = Value::Object(map);
+        match env_type.as_str() {
+            "kafka" => deserialize_env(value, &env_type, &name).map(EnvConfig::Kafka),
+            "local" => deserialize_env(value, &env_type, &name).map(EnvConfig::Local),
+            _ => Err(D::Error::custom(format!(
+                "Invalid type '{env_type}' of environment{name}, expected one of {}",
+                ENV_TYPES.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Deserializes the environment config and names the offending field on errors.
+fn deserialize_env<T: DeserializeOwned, E: Error>(
+    value: Value,
+    env_type: &str,
+    name: &str,
+) -> Result<T, E> {
+    serde_path_to_error::deserialize(value).map_err(|err| {
+        let field = match err.path().to_string().as_str() {
+            "." => String::new(),
+            path => format!("{path} in "),
+        };
+        E::custom(format!(
+            "Invalid {field}{env_type} environment{name} ({})",
+            err.into_inner()
+        ))
+    })
+}
+
 impl EnvConfig {
+    /// Returns the type of an environment [config] without a `type` based on its fields.
+    pub(crate) fn untagged_type(config: &Map<String, Value>) -> Option<&'static str> {
+        if config.contains_key("kafka") {
+            Some("kafka")
+        } else if config.contains_key("url") {
+            Some("local")
+        } else {
+            None
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            EnvConfig::Kafka(c) => &c.name,
+            EnvConfig::Local(c) => &c.name,
+        }
+    }
+
     pub fn program_allowlist(&self) -> &[String] {
         match self {
             EnvConfig::Kafka(c) => &c.program_allowlist,
             EnvConfig::Local(c) => &c.program_allowlist,
         }
     }
+
+    pub fn topics(&self, config: &Config) -> EnvTopics {
+        match self {
+            EnvConfig::Kafka(c) => c.topics(config),
+            EnvConfig::Local(c) => c.topics(config),
+        }
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        match self {
+            EnvConfig::Kafka(c) => c.commitment,
+            EnvConfig::Local(c) => c.commitment,
+        }
+    }
+
+    pub fn transaction_match(&self) -> TransactionMatch {
+        match self {
+            EnvConfig::Kafka(c) => c.transaction_match,
+            EnvConfig::Local(c) => c.transaction_match,
+        }
+    }
+
+    pub fn exclude_vote_transactions(&self) -> bool {
+        match self {
+            EnvConfig::Kafka(c) => c.exclude_vote_transactions,
+            EnvConfig::Local(c) => c.exclude_vote_transactions,
+        }
+    }
+
+    pub fn exclude_failed_transactions(&self) -> bool {
+        match self {
+            EnvConfig::Kafka(c) => c.exclude_failed_transactions,
+            EnvConfig::Local(c) => c.exclude_failed_transactions,
+        }
+    }
+
+    /// Returns the event policy of this environment, falling back to the global one of [config].
+    pub fn policy(&self, config: &Config) -> EventPolicy {
+        let (
+            publish_all_accounts,
+            publish_account_deletions,
+            publish_accounts_without_signature,
+            compact_account_updates,
+        ) = match self {
+            EnvConfig::Kafka(c) => (
+                c.publish_all_accounts,
+                c.publish_account_deletions,
+                c.publish_accounts_without_signature,
+                c.compact_account_updates,
+            ),
+            EnvConfig::Local(c) => (
+                c.publish_all_accounts,
+                c.publish_account_deletions,
+                c.publish_accounts_without_signature,
+                c.compact_account_updates,
+            ),
+        };
+        EventPolicy {
+            publish_all_accounts: publish_all_accounts.unwrap_or(config.publish_all_accounts),
+            publish_account_deletions: publish_account_deletions
+                .unwrap_or(config.publish_account_deletions),
+            publish_accounts_without_signature: publish_accounts_without_signature
+                .unwrap_or(config.publish_accounts_without_signature),
+            compact_account_updates: compact_account_updates
+                .unwrap_or(config.compact_account_updates),
+        }
+    }
+
+    pub fn queue(&self) -> QueueConfig {
+        match self {
+            EnvConfig::Kafka(c) => QueueConfig {
+                capacity: c.queue_capacity,
+                workers: c.queue_workers,
+                overflow_policy: c.queue_overflow_policy,
+            },
+            EnvConfig::Local(c) => QueueConfig {
+                capacity: c.queue_capacity,
+                workers: c.queue_workers,
+                overflow_policy: c.queue_overflow_policy,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn deserialize_err(value: Value) -> String {
+        serde_json::from_value::<EnvConfig>(value)
+            .err()
+            .expect("should fail to deserialize")
+            .to_string()
+    }
+
+    #[test]
+    fn tagged_and_untagged_envs() {
+        let config = Config::read_from("test/fixtures/configs/multi-env-typed.json")
+            .expect("should deserialize config");
+        let types = config
+            .environments
+            .iter()
+            .map(|env| match env {
+                EnvConfig::Kafka(c) => ("kafka", c.name.as_str()),
+                EnvConfig::Local(c) => ("local", c.name.as_str()),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![("kafka", "dev"), ("local", "local"), ("kafka", "stage")]
+        );
+        assert_eq!(config.validate(false), Ok(()));
+
+        // The type is kept when the config is printed
+        let json = config.to_redacted_json().unwrap();
+        assert_eq!(json["environments"][2]["type"], "kafka");
+        assert_eq!(json["environments"][1]["type"], "local");
+    }
+
+    #[test]
+    fn env_errors() {
+        assert_eq!(
+            deserialize_err(json!({ "type": "kafka", "name": "dev", "url": "http://localhost" })),
+            "Invalid kafka environment 'dev' (missing field `kafka`)"
+        );
+        assert_eq!(
+            deserialize_err(
+                json!({ "type": "kafka", "name": "dev", "kafka": {}, "commitment": "final" })
+            ),
+            "Invalid commitment in kafka environment 'dev' (unknown variant `final`, \
+             expected one of `processed`, `confirmed`, `rooted`)"
+        );
+        assert_eq!(
+            deserialize_err(
+                json!({ "name": "dev", "url": "http://localhost", "queue_capacity": -1 })
+            ),
+            "Invalid queue_capacity in local environment 'dev' (invalid value: integer \
+             `-1`, expected usize)"
+        );
+        assert_eq!(
+            deserialize_err(json!({ "type": "kafak", "name": "dev", "kafka": {} })),
+            "Invalid type 'kafak' of environment 'dev', expected one of kafka, local"
+        );
+        assert_eq!(
+            deserialize_err(json!({ "name": "dev", "kafak": {} })),
+            "Need to provide a type, a kafka config or a url for environment 'dev'"
+        );
+    }
+
+    #[test]
+    fn env_errors_name_the_config_path() {
+        let err = serde_path_to_error::deserialize::<_, Config>(json!({
+            "environments": [{ "name": "dev", "kafka": { "bootstrap.servers": 9092 } }]
+        }))
+        .map_err(|err| err.to_string());
+        assert_eq!(
+            err.err().as_deref(),
+            Some(
+                "environments[0]: Invalid kafka.bootstrap.servers in kafka environment 'dev' \
+                 (invalid type: integer `9092`, expected a string)"
+            )
+        );
+    }
 }
@@ -0,0 +1,78 @@
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    owner_cache::OwnerCache, publisher::Publisher, AccountCreatedEvent, PluginError, PluginResult,
+};
+
+use super::replica_transaction_info::ReplicaTransactionInfo;
+
+impl AccountCreatedEvent {
+    pub fn new(
+        account_index: usize,
+        account: Pubkey,
+        owner: Option<Pubkey>,
+        tx: &ReplicaTransactionInfo,
+    ) -> Self {
+        Self {
+            slot: tx.slot(),
+            pubkey: account.to_bytes().to_vec(),
+            lamports: tx.transaction_status_meta.post_balances[account_index],
+            owner: owner.map(|owner| owner.to_bytes().to_vec()),
+            txn_signature: tx.signature().as_ref().to_vec(),
+            transaction_index: tx.transaction_index().map(|index| index as u64),
+            account_index: account_index as u64,
+        }
+    }
+}
+
+/// Publishes an [AccountCreatedEvent] for each account created in [tx] to the
+/// publishers that want its owner. Unless the owner is known from a previous account update,
+/// the creation is published to the publishers that want any other account of the transaction.
+pub fn publish_created_account_events<F>(
+    publishers: &[&Publisher],
+    tx: &ReplicaTransactionInfo,
+    owner_cache: &OwnerCache,
+    publish: F,
+) -> Vec<PluginError>
+where
+    F: Fn(&Publisher, AccountCreatedEvent) -> PluginResult<()>,
+{
+    let publishers = publishers
+        .iter()
+        .filter(|p| p.wants_account_created())
+        .collect::<Vec<_>>();
+    if publishers.is_empty() {
+        return vec![];
+    }
+
+    let created_accounts = tx.account_addresses_with_zero_pre_balance();
+    if created_accounts.is_empty() {
+        return vec![];
+    }
+
+    let account_keys = tx.account_keys();
+    let other_keys = account_keys
+        .iter()
+        .filter(|key| !created_accounts.iter().any(|(_, created)| created == *key))
+        .collect::<Vec<_>>();
+
+    let mut errors = vec![];
+    for (account_index, created_account) in created_accounts {
+        let owner = owner_cache.get(&created_account);
+        let event = AccountCreatedEvent::new(account_index, created_account, owner, tx);
+        for publisher in &publishers {
+            let wanted = match owner {
+                Some(owner) => publisher.wants_account_key(owner.as_ref()),
+                None => other_keys
+                    .iter()
+                    .any(|key| publisher.wants_account_key(key.as_ref())),
+            };
+            if wanted {
+                if let Err(err) = publish(publisher, event.clone()) {
+                    errors.push(err)
+                }
+            }
+        }
+    }
+    errors
+}
@@ -1,2 +1,4 @@
+pub mod account_created_event;
+pub mod replica_account_info;
 pub mod replica_transaction_info;
 pub mod update_account_event;
@@ -0,0 +1,69 @@
+use solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaAccountInfoVersions;
+use solana_sdk::signature::Signature;
+
+use crate::prom::REPLICA_INFO_VERSIONS_TOTAL;
+
+/// The account update fields provided by all versions of [ReplicaAccountInfoVersions].
+/// Fields that older versions do not provide are left unset.
+pub struct ReplicaAccountInfo<'a> {
+    pub pubkey: &'a [u8],
+    pub lamports: u64,
+    pub owner: &'a [u8],
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: &'a [u8],
+    pub write_version: u64,
+    /// First signature of the transaction that caused this account modification.
+    /// Not provided by [ReplicaAccountInfoVersions::V0_0_1].
+    pub txn_signature: Option<&'a Signature>,
+}
+
+impl<'a> From<ReplicaAccountInfoVersions<'a>> for ReplicaAccountInfo<'a> {
+    fn from(account: ReplicaAccountInfoVersions<'a>) -> Self {
+        let (version, info) = match account {
+            ReplicaAccountInfoVersions::V0_0_1(info) => (
+                "0.0.1",
+                Self {
+                    pubkey: info.pubkey,
+                    lamports: info.lamports,
+                    owner: info.owner,
+                    executable: info.executable,
+                    rent_epoch: info.rent_epoch,
+                    data: info.data,
+                    write_version: info.write_version,
+                    txn_signature: None,
+                },
+            ),
+            ReplicaAccountInfoVersions::V0_0_2(info) => (
+                "0.0.2",
+                Self {
+                    pubkey: info.pubkey,
+                    lamports: info.lamports,
+                    owner: info.owner,
+                    executable: info.executable,
+                    rent_epoch: info.rent_epoch,
+                    data: info.data,
+                    write_version: info.write_version,
+                    txn_signature: info.txn_signature,
+                },
+            ),
+            ReplicaAccountInfoVersions::V0_0_3(info) => (
+                "0.0.3",
+                Self {
+                    pubkey: info.pubkey,
+                    lamports: info.lamports,
+                    owner: info.owner,
+                    executable: info.executable,
+                    rent_epoch: info.rent_epoch,
+                    data: info.data,
+                    write_version: info.write_version,
+                    txn_signature: info.txn.map(|txn| txn.signature()),
+                },
+            ),
+        };
+        REPLICA_INFO_VERSIONS_TOTAL
+            .with_label_values(&["account", version])
+            .inc();
+        info
+    }
+}
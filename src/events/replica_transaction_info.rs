@@ -2,12 +2,15 @@ use std::ops::Deref;
 
 use log::debug;
 use solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfoV2;
-use solana_program::{message::SanitizedMessage, pubkey::Pubkey, slot_history::Slot};
+use solana_program::{message::AccountKeys, pubkey::Pubkey, slot_history::Slot};
 use solana_sdk::signature::Signature;
 
+use crate::TransactionMatch;
+
 pub struct ReplicaTransactionInfo<'a> {
     info: &'a ReplicaTransactionInfoV2<'a>,
     slot: Slot,
+    index: Option<usize>,
 }
 
 impl<'a> Deref for ReplicaTransactionInfo<'a> {
@@ -19,11 +22,27 @@ impl<'a> Deref for ReplicaTransactionInfo<'a> {
 }
 
 impl<'a> ReplicaTransactionInfo<'a> {
-    pub fn new(info: &'a ReplicaTransactionInfoV2<'_>, slot: u64) -> Self {
-        Self { info, slot }
+    /// The [index] of the transaction in its block is `None` when the validator does not
+    /// provide it, in which case the `index` of [info] is meaningless.
+    pub fn new(info: &'a ReplicaTransactionInfoV2<'_>, slot: u64, index: Option<usize>) -> Self {
+        Self { info, slot, index }
+    }
+
+    /// Returns the index and address of the accounts whose lamports dropped to 0.
+    pub fn account_addresses_with_zero_post_balance(&self) -> Vec<(usize, Pubkey)> {
+        self.account_addresses_with_balances(|pre, post| pre > 0 && post == 0)
+    }
+
+    /// Returns the index and address of the accounts whose lamports went from 0 to a positive
+    /// balance.
+    pub fn account_addresses_with_zero_pre_balance(&self) -> Vec<(usize, Pubkey)> {
+        self.account_addresses_with_balances(|pre, post| pre == 0 && post > 0)
     }
 
-    pub fn account_addresses_with_zero_post_balance(&self) -> Vec<Pubkey> {
+    fn account_addresses_with_balances(
+        &self,
+        matches: impl Fn(u64, u64) -> bool,
+    ) -> Vec<(usize, Pubkey)> {
         if self.is_vote {
             return vec![];
         }
@@ -32,8 +51,11 @@ impl<'a> ReplicaTransactionInfo<'a> {
             .post_balances
             .iter()
             .enumerate()
-            .filter(|(idx, balance)| {
-                **balance == 0 && self.transaction_status_meta.pre_balances[*idx] > 0
+            .filter(|(idx, post)| {
+                self.transaction_status_meta
+                    .pre_balances
+                    .get(*idx)
+                    .map_or(false, |pre| matches(*pre, **post))
             })
             .map(|(idx, _)| idx)
             .collect::<Vec<usize>>();
@@ -59,24 +81,271 @@ impl<'a> ReplicaTransactionInfo<'a> {
                         self.signature().to_string()
                     );
                 }
-                key
+                key.map(|key| (idx, *key))
             })
-            .cloned()
             .collect::<Vec<_>>()
     }
 
-    pub(crate) fn account_keys(&self) -> &Vec<Pubkey> {
-        match self.transaction.message() {
-            SanitizedMessage::Legacy(legacy) => &legacy.message.account_keys,
-            SanitizedMessage::V0(v0) => &v0.message.account_keys,
+    /// Returns the account keys matched against the program allowlist for [transaction_match].
+    pub(crate) fn matched_keys(&self, transaction_match: TransactionMatch) -> Vec<Pubkey> {
+        let message = self.transaction.message();
+        let account_keys = message.account_keys();
+        match transaction_match {
+            TransactionMatch::AnyAccount => account_keys.iter().copied().collect(),
+            TransactionMatch::ProgramIds => {
+                let inner_program_id_indexes = self
+                    .transaction_status_meta
+                    .inner_instructions
+                    .iter()
+                    .flatten()
+                    .flat_map(|ixs| ixs.instructions.iter())
+                    .map(|ix| ix.instruction.program_id_index);
+                let mut program_ids = message
+                    .instructions()
+                    .iter()
+                    .map(|ix| ix.program_id_index)
+                    .chain(inner_program_id_indexes)
+                    .filter_map(|idx| account_keys.get(idx as usize))
+                    .copied()
+                    .collect::<Vec<_>>();
+                program_ids.sort();
+                program_ids.dedup();
+                program_ids
+            }
+            TransactionMatch::WritableAccounts => account_keys
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| message.is_writable(*idx))
+                .map(|(_, key)| *key)
+                .collect(),
         }
     }
 
+    /// Returns all account keys of the transaction, including the addresses loaded from
+    /// address lookup tables, in the order of the balances in the transaction status meta.
+    pub(crate) fn account_keys(&self) -> AccountKeys {
+        self.transaction.message().account_keys()
+    }
+
     pub(crate) fn slot(&self) -> u64 {
         self.slot
     }
 
+    /// Returns the index of the transaction in its block if known.
+    pub(crate) fn transaction_index(&self) -> Option<usize> {
+        self.index
+    }
+
     pub(crate) fn signature(&self) -> &Signature {
         self.info.transaction.signature()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_program::{
+        hash::Hash,
+        instruction::{AccountMeta, CompiledInstruction, Instruction},
+        message::{
+            v0::{self, LoadedAddresses, MessageAddressTableLookup},
+            Message, MessageHeader, SimpleAddressLoader, VersionedMessage,
+        },
+    };
+    use solana_sdk::{
+        signature::Keypair,
+        signer::Signer,
+        transaction::{SanitizedTransaction, Transaction, VersionedTransaction},
+    };
+    use solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta};
+
+    use super::*;
+
+    struct V0Transaction {
+        transaction: SanitizedTransaction,
+        payer: Pubkey,
+        program: Pubkey,
+        loaded_writable: Pubkey,
+        loaded_readonly: Pubkey,
+    }
+
+    /// Creates a V0 transaction invoking a program with one writable and one readonly account
+    /// loaded from an address lookup table.
+    fn v0_transaction() -> V0Transaction {
+        let payer = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let loaded_writable = Pubkey::new_unique();
+        let loaded_readonly = Pubkey::new_unique();
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![],
+                vec![0, 2, 3],
+            )],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        };
+        let transaction = SanitizedTransaction::try_create(
+            VersionedTransaction {
+                signatures: vec![Default::default()],
+                message: VersionedMessage::V0(message),
+            },
+            Hash::default(),
+            Some(false),
+            SimpleAddressLoader::Enabled(LoadedAddresses {
+                writable: vec![loaded_writable],
+                readonly: vec![loaded_readonly],
+            }),
+        )
+        .unwrap();
+        V0Transaction {
+            transaction,
+            payer,
+            program,
+            loaded_writable,
+            loaded_readonly,
+        }
+    }
+
+    fn transaction_info<'a>(
+        transaction: &'a SanitizedTransaction,
+        transaction_status_meta: &'a TransactionStatusMeta,
+    ) -> ReplicaTransactionInfoV2<'a> {
+        ReplicaTransactionInfoV2 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction,
+            transaction_status_meta,
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn account_keys_include_loaded_addresses() {
+        let tx = v0_transaction();
+        let meta = TransactionStatusMeta::default();
+        let info = transaction_info(&tx.transaction, &meta);
+        let info = ReplicaTransactionInfo::new(&info, 1, Some(0));
+
+        assert_eq!(
+            info.account_keys().iter().copied().collect::<Vec<_>>(),
+            vec![tx.payer, tx.program, tx.loaded_writable, tx.loaded_readonly]
+        );
+    }
+
+    #[test]
+    fn zero_post_balance_of_loaded_addresses() {
+        let tx = v0_transaction();
+        let meta = TransactionStatusMeta {
+            pre_balances: vec![10, 1, 5, 7],
+            post_balances: vec![15, 1, 0, 7],
+            ..Default::default()
+        };
+        let info = transaction_info(&tx.transaction, &meta);
+        let info = ReplicaTransactionInfo::new(&info, 1, Some(0));
+
+        assert_eq!(
+            info.account_addresses_with_zero_post_balance(),
+            vec![(2, tx.loaded_writable)]
+        );
+        assert_eq!(
+            info.matched_keys(TransactionMatch::WritableAccounts),
+            vec![tx.payer, tx.loaded_writable]
+        );
+    }
+
+    #[test]
+    fn zero_pre_balance_of_loaded_addresses() {
+        let tx = v0_transaction();
+        let meta = TransactionStatusMeta {
+            pre_balances: vec![10, 0, 0, 0],
+            post_balances: vec![5, 0, 0, 5],
+            ..Default::default()
+        };
+        let info = transaction_info(&tx.transaction, &meta);
+        let info = ReplicaTransactionInfo::new(&info, 1, Some(0));
+
+        assert_eq!(
+            info.account_addresses_with_zero_pre_balance(),
+            vec![(3, tx.loaded_readonly)]
+        );
+        assert!(info.account_addresses_with_zero_post_balance().is_empty());
+    }
+
+    #[test]
+    fn matched_keys() {
+        let payer = Keypair::new();
+        let writable = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let program = Pubkey::new_unique();
+        let inner_program = Pubkey::new_unique();
+        let ix = Instruction::new_with_bytes(
+            program,
+            &[],
+            vec![
+                AccountMeta::new(writable, false),
+                AccountMeta::new_readonly(readonly, false),
+                AccountMeta::new_readonly(inner_program, false),
+            ],
+        );
+        let message = Message::new(&[ix], Some(&payer.pubkey()));
+        let inner_program_idx = message
+            .account_keys
+            .iter()
+            .position(|key| *key == inner_program)
+            .unwrap();
+        let transaction = SanitizedTransaction::from_transaction_for_tests(Transaction::new(
+            &[&payer],
+            message,
+            Hash::default(),
+        ));
+        let transaction_status_meta = TransactionStatusMeta {
+            inner_instructions: Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(
+                        inner_program_idx as u8,
+                        vec![],
+                        vec![],
+                    ),
+                    stack_height: Some(2),
+                }],
+            }]),
+            ..Default::default()
+        };
+        let info = transaction_info(&transaction, &transaction_status_meta);
+        let info = ReplicaTransactionInfo::new(&info, 1, Some(0));
+
+        let sorted = |mut keys: Vec<Pubkey>| {
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            sorted(info.matched_keys(TransactionMatch::AnyAccount)),
+            sorted(vec![
+                payer.pubkey(),
+                writable,
+                readonly,
+                program,
+                inner_program
+            ])
+        );
+        assert_eq!(
+            info.matched_keys(TransactionMatch::ProgramIds),
+            sorted(vec![program, inner_program])
+        );
+        assert_eq!(
+            sorted(info.matched_keys(TransactionMatch::WritableAccounts)),
+            sorted(vec![payer.pubkey(), writable])
+        );
+    }
+}
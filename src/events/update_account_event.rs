@@ -1,14 +1,17 @@
 use std::sync::{Arc, Mutex};
 
-use solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfoV2;
-use solana_program::{pubkey::Pubkey, slot_history::Slot};
+use solana_program::pubkey::Pubkey;
 
-use crate::{publisher::Publisher, PluginError, UpdateAccountEvent};
+use crate::{
+    owner_cache::OwnerCache, publisher::Publisher, AccountUpdateSource, PluginError, PluginResult,
+    UpdateAccountEvent,
+};
 
 use super::replica_transaction_info::ReplicaTransactionInfo;
 
 impl UpdateAccountEvent {
     pub fn for_account_deletion(
+        account_index: usize,
         account: Pubkey,
         owner: Vec<u8>,
         tx: &ReplicaTransactionInfo,
@@ -27,24 +30,38 @@ impl UpdateAccountEvent {
             // we published an account_update incremented by 1
             write_version,
             txn_signature: Some(signature),
+            is_startup: false,
+            source: AccountUpdateSource::TransactionDeletion.into(),
+            is_deleted: true,
+            transaction_index: tx.transaction_index().map(|index| index as u64),
+            account_index: Some(account_index as u64),
         }
     }
 }
 
-pub fn publish_deleted_account_events(
+pub fn publish_deleted_account_events<F>(
     publishers: &[&Publisher],
-    transaction: &ReplicaTransactionInfoV2,
-    slot: Slot,
+    tx: &ReplicaTransactionInfo,
     last_published_write_version: &Arc<Mutex<u64>>,
-) -> Vec<PluginError> {
+    owner_cache: &OwnerCache,
+    publish: F,
+) -> Vec<PluginError>
+where
+    F: Fn(&Publisher, UpdateAccountEvent) -> PluginResult<()>,
+{
+    // Neither look for deleted accounts nor evict them from the owner cache
+    if publishers.is_empty() {
+        return vec![];
+    }
+
     let events =
-        create_deleted_account_events(publishers, transaction, slot, last_published_write_version);
+        create_deleted_account_events(publishers, tx, last_published_write_version, owner_cache);
     let mut errors = vec![];
     for event in events.into_iter() {
         let owner = &event.owner;
         for publisher in publishers {
             if publisher.wants_account_key(owner) {
-                if let Err(err) = publisher.update_account(event.clone()) {
+                if let Err(err) = publish(publisher, event.clone()) {
                     errors.push(err)
                 }
             }
@@ -55,11 +72,10 @@ pub fn publish_deleted_account_events(
 
 fn create_deleted_account_events(
     publishers: &[&Publisher],
-    transaction: &ReplicaTransactionInfoV2,
-    slot: Slot,
+    tx: &ReplicaTransactionInfo,
     last_published_write_version: &Arc<Mutex<u64>>,
+    owner_cache: &OwnerCache,
 ) -> Vec<UpdateAccountEvent> {
-    let tx = ReplicaTransactionInfo::new(transaction, slot);
     let deleted_accounts = tx.account_addresses_with_zero_post_balance();
     if deleted_accounts.is_empty() {
         return vec![];
@@ -67,12 +83,12 @@ fn create_deleted_account_events(
 
     let account_keys = tx.account_keys();
 
-    // We assume that one of the accounts (not deleted) is the program that owns the
-    // deleted account and thus create an UpdateAccountEvent for each, given that a
-    // publisher is interested in it.
+    // Unless we know the owner of a deleted account from a previous account update, we
+    // assume that one of the accounts (not deleted) is the program that owns it and thus
+    // create an UpdateAccountEvent for each, given that a publisher is interested in it.
     let programs_we_want = account_keys
         .iter()
-        .filter(|key| !deleted_accounts.contains(key))
+        .filter(|key| !deleted_accounts.iter().any(|(_, deleted)| deleted == *key))
         .filter(|key| {
             publishers
                 .iter()
@@ -80,24 +96,25 @@ fn create_deleted_account_events(
         })
         .collect::<Vec<_>>();
 
-    if programs_we_want.is_empty() {
-        return vec![];
-    };
-
     let write_version = *last_published_write_version
         .lock()
         .expect("write_version Mutex poisend")
         + 1;
     deleted_accounts
         .into_iter()
-        .flat_map(|deleted_account| {
-            programs_we_want
-                .iter()
+        .flat_map(|(account_index, deleted_account)| {
+            let owners = match owner_cache.remove(&deleted_account) {
+                Some(owner) => vec![owner],
+                None => programs_we_want.iter().map(|owner| **owner).collect(),
+            };
+            owners
+                .into_iter()
                 .map(|owner| {
                     UpdateAccountEvent::for_account_deletion(
+                        account_index,
                         deleted_account,
                         owner.to_bytes().to_vec(),
-                        &tx,
+                        tx,
                         write_version,
                     )
                 })
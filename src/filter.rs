@@ -12,17 +12,126 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use serde::{Deserialize, Serialize};
+
+use solana_geyser_plugin_interface::geyser_plugin_interface::Result as PluginResult;
+
 use crate::allowlist::Allowlist;
-use crate::EnvConfig;
+use crate::{Commitment, Config, EnvConfig, TransactionEvent};
+
+/// Which account keys of a transaction are matched against the program allowlist to decide
+/// whether a transaction is published.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionMatch {
+    /// Any account key of the transaction.
+    #[default]
+    AnyAccount,
+    /// The ids of the programs invoked by the transaction, including inner instructions.
+    ProgramIds,
+    /// The accounts written to by the transaction.
+    WritableAccounts,
+}
+
+/// The category of a transaction, used to exclude or route vote and failed transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransactionCategory {
+    Regular,
+    /// A simple vote transaction, which takes precedence over [TransactionCategory::Failed].
+    Vote,
+    Failed,
+}
+
+impl TransactionCategory {
+    pub fn new(is_vote: bool, is_status_err: bool) -> Self {
+        if is_vote {
+            TransactionCategory::Vote
+        } else if is_status_err {
+            TransactionCategory::Failed
+        } else {
+            TransactionCategory::Regular
+        }
+    }
+
+    pub fn of(ev: &TransactionEvent) -> Self {
+        Self::new(
+            ev.is_vote,
+            ev.transaction_status_meta
+                .as_ref()
+                .map_or(false, |meta| meta.is_status_err),
+        )
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionCategory::Regular => "regular",
+            TransactionCategory::Vote => "vote",
+            TransactionCategory::Failed => "failed",
+        }
+    }
+}
+
+/// Which kinds of events are published to an environment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EventPolicy {
+    pub publish_all_accounts: bool,
+    pub publish_account_deletions: bool,
+    pub publish_accounts_without_signature: bool,
+    pub compact_account_updates: bool,
+}
 
 pub struct Filter {
     program_allowlist: Allowlist,
+    program_denylist: Allowlist,
+    /// Whether all programs but those in the [program_denylist] are wanted while the
+    /// [program_allowlist] is empty.
+    wants_all_on_empty_allow_list: bool,
+    policy: EventPolicy,
+    commitment: Commitment,
+    transaction_match: TransactionMatch,
+    exclude_vote_transactions: bool,
+    exclude_failed_transactions: bool,
 }
 
 impl Filter {
-    pub fn new(config: &EnvConfig) -> Self {
-        Self {
-            program_allowlist: Allowlist::new_from_config(config).unwrap(),
+    pub fn new(config: &EnvConfig, global_config: &Config) -> PluginResult<Self> {
+        // Local environments publish all accounts without an allowlist, Kafka environments only
+        // if the allowlist is neither configured nor fetched from a URL
+        let wants_all_on_empty_allow_list = match config {
+            EnvConfig::Kafka(c) => {
+                c.program_allowlist.is_empty() && c.program_allowlist_url.is_empty()
+            }
+            EnvConfig::Local(_) => true,
+        };
+        Ok(Self {
+            program_allowlist: Allowlist::new_from_config(config)?,
+            program_denylist: Allowlist::new_denylist_from_config(config)?,
+            wants_all_on_empty_allow_list,
+            policy: config.policy(global_config),
+            commitment: config.commitment(),
+            transaction_match: config.transaction_match(),
+            exclude_vote_transactions: config.exclude_vote_transactions(),
+            exclude_failed_transactions: config.exclude_failed_transactions(),
+        })
+    }
+
+    pub fn policy(&self) -> EventPolicy {
+        self.policy
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.commitment
+    }
+
+    pub fn transaction_match(&self) -> TransactionMatch {
+        self.transaction_match
+    }
+
+    pub fn wants_transaction_category(&self, category: TransactionCategory) -> bool {
+        match category {
+            TransactionCategory::Regular => true,
+            TransactionCategory::Vote => !self.exclude_vote_transactions,
+            TransactionCategory::Failed => !self.exclude_failed_transactions,
         }
     }
 
@@ -30,19 +139,79 @@ impl Filter {
         self.program_allowlist.clone()
     }
 
+    pub fn get_denylist(&self) -> Allowlist {
+        self.program_denylist.clone()
+    }
+
     pub fn allow_list_is_empty(&self) -> bool {
         self.program_allowlist.len() == 0
     }
 
-    pub fn wants_account_key(
-        &self,
-        account_key: &[u8],
-        wants_all_on_empty_allow_list: bool,
-    ) -> bool {
-        if self.program_allowlist.len() > 0 {
+    /// Returns whether accounts owned by [account_key] are wanted. Programs in the denylist are
+    /// never wanted, even if they are in the allowlist.
+    pub fn wants_account_key(&self, account_key: &[u8]) -> bool {
+        if self.program_denylist.contains(account_key) {
+            false
+        } else if self.program_allowlist.len() > 0 {
             self.program_allowlist.wants_program(account_key)
         } else {
-            wants_all_on_empty_allow_list
+            self.wants_all_on_empty_allow_list
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use solana_program::pubkey::Pubkey;
+
+    use super::*;
+    use crate::env_config::{EnvConfigKafka, EnvConfigLocal};
+
+    const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+    const VOTE_PROGRAM: &str = "Vote111111111111111111111111111111111111111";
+
+    fn key(pubkey: &str) -> [u8; 32] {
+        Pubkey::from_str(pubkey).unwrap().to_bytes()
+    }
+
+    fn wanted(filter: &Filter) -> Vec<bool> {
+        [TOKEN_PROGRAM, TOKEN_2022_PROGRAM, VOTE_PROGRAM]
+            .iter()
+            .map(|program| filter.wants_account_key(&key(program)))
+            .collect()
+    }
+
+    #[test]
+    fn denylist_takes_precedence() {
+        let config = Config::default();
+
+        let allowlist_and_denylist = EnvConfig::Kafka(EnvConfigKafka {
+            program_allowlist: vec![TOKEN_PROGRAM.to_string(), TOKEN_2022_PROGRAM.to_string()],
+            program_denylist: vec![TOKEN_2022_PROGRAM.to_string()],
+            ..EnvConfigKafka::default()
+        });
+        let filter = Filter::new(&allowlist_and_denylist, &config).unwrap();
+        assert_eq!(wanted(&filter), vec![true, false, false]);
+
+        // Without an allowlist all programs but the denied ones are published
+        let denylist = EnvConfig::Kafka(EnvConfigKafka {
+            program_denylist: vec![VOTE_PROGRAM.to_string()],
+            ..EnvConfigKafka::default()
+        });
+        let filter = Filter::new(&denylist, &config).unwrap();
+        assert_eq!(wanted(&filter), vec![true, true, false]);
+
+        let local_denylist = EnvConfig::Local(EnvConfigLocal {
+            program_denylist: vec![TOKEN_PROGRAM.to_string()],
+            ..EnvConfigLocal::default()
+        });
+        let filter = Filter::new(&local_denylist, &config).unwrap();
+        assert_eq!(wanted(&filter), vec![false, true, true]);
+
+        // Kafka environments need an allowlist or a denylist
+        assert!(Filter::new(&EnvConfig::Kafka(EnvConfigKafka::default()), &config).is_err());
+    }
+}
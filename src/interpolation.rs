@@ -0,0 +1,176 @@
+use std::{env, fs};
+
+use serde_json::Value;
+
+const FILE_PREFIX: &str = "file:";
+
+/// Resolves the references in all string values of [value]:
+/// - `${VAR}` is replaced with the value of the environment variable `VAR`
+/// - `${file:/path}` is replaced with the contents of that file without the trailing newline
+/// - `$${` is replaced with a literal `${`
+///
+/// This allows to keep secrets like passwords out of the config file.
+pub(crate) fn interpolate(value: &mut Value) -> Result<(), String> {
+    interpolate_with(value, "", &|name| env::var(name).ok())
+}
+
+fn interpolate_with(
+    value: &mut Value,
+    path: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<(), String> {
+    match value {
+        Value::String(s) => {
+            *s = interpolate_string(s, path, lookup)?;
+        }
+        Value::Array(values) => {
+            for (idx, value) in values.iter_mut().enumerate() {
+                interpolate_with(value, &format!("{path}[{idx}]"), lookup)?;
+            }
+        }
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                interpolate_with(value, &path, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_string(
+    s: &str,
+    path: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find("${") {
+        if let Some(escaped) = rest[..start].strip_suffix('$') {
+            result.push_str(escaped);
+            result.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start + 2..].find('}') else {
+            return Err(format!("Unterminated ${{ in {path}"));
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        let value = match name.strip_prefix(FILE_PREFIX) {
+            Some(file) => fs::read_to_string(file)
+                .map(|contents| contents.trim_end_matches(['\r', '\n']).to_string())
+                .map_err(|err| {
+                    format!("Failed to read file {file} referenced in {path} ({err})")
+                })?,
+            None => lookup(name).ok_or_else(|| {
+                format!("Environment variable {name} referenced in {path} is not set")
+            })?,
+        };
+        result.push_str(&value);
+        rest = &rest[start + 2 + len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "SASL_PASSWORD" => Some("hunter2".to_string()),
+            "TOKEN" => Some("secret-token".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn interpolates_env_vars() {
+        let mut value = json!({
+            "shutdown_timeout_ms": 100,
+            "environments": [{
+                "program_allowlist_auth": "Bearer ${TOKEN}",
+                "kafka": { "sasl.password": "${SASL_PASSWORD}", "sasl.username": "user" }
+            }]
+        });
+        interpolate_with(&mut value, "", &lookup).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "shutdown_timeout_ms": 100,
+                "environments": [{
+                    "program_allowlist_auth": "Bearer secret-token",
+                    "kafka": { "sasl.password": "hunter2", "sasl.username": "user" }
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn fails_on_missing_env_vars() {
+        let mut value = json!({ "environments": [{ "kafka": { "sasl.password": "${MISSING}" } }] });
+        assert_eq!(
+            interpolate_with(&mut value, "", &lookup),
+            Err(
+                "Environment variable MISSING referenced in environments[0].kafka.sasl.password \
+                 is not set"
+                    .to_string()
+            )
+        );
+
+        let mut value = json!({ "program_allowlist_auth": "Bearer ${TOKEN" });
+        assert_eq!(
+            interpolate_with(&mut value, "", &lookup),
+            Err("Unterminated ${ in program_allowlist_auth".to_string())
+        );
+    }
+
+    #[test]
+    fn interpolates_files() {
+        let path = env::temp_dir().join(format!("geyser-kafka-secret-{}", std::process::id()));
+        writeln!(fs::File::create(&path).unwrap(), "hunter2").unwrap();
+
+        let mut value = json!({
+            "sasl.password": format!("${{file:{}}}", path.display()),
+            "program_allowlist_auth": format!("Bearer ${{file:{}}}", path.display()),
+        });
+        interpolate_with(&mut value, "", &lookup).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            value,
+            json!({ "sasl.password": "hunter2", "program_allowlist_auth": "Bearer hunter2" })
+        );
+
+        let mut value = json!({ "sasl.password": format!("${{file:{}}}", path.display()) });
+        assert!(interpolate_with(&mut value, "", &lookup)
+            .unwrap_err()
+            .starts_with("Failed to read file"));
+    }
+
+    #[test]
+    fn keeps_literal_values() {
+        let mut value = json!({
+            "update_account_topic": "file:accounts",
+            "sasl.password": "pa$${TOKEN}-${TOKEN}",
+        });
+        interpolate_with(&mut value, "", &lookup).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "update_account_topic": "file:accounts",
+                "sasl.password": "pa${TOKEN}-secret-token",
+            })
+        );
+    }
+}
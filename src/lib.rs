@@ -15,33 +15,48 @@
 // The generated code (via prost) does not add `Eq` derive which causes clippy warnings since Rust
 // v1.63. This is the only way to suppress those since we cannot edit the generated file.
 #![allow(clippy::derive_partial_eq_without_eq)]
+// Newer clippy versions flag unit structs that are constructed via `default()`. The producer
+// context is constructed that way so that it can gain fields without touching its call sites.
+#![allow(clippy::default_constructed_unit_structs)]
 
 use solana_geyser_plugin_interface::geyser_plugin_interface::GeyserPlugin;
 
 mod allowlist;
 mod cluster;
+mod commitment;
+mod compaction;
 mod config;
 mod env_config;
 mod errors;
 mod event;
 pub mod events;
 mod filter;
+mod interpolation;
+mod owner_cache;
 mod plugin;
 mod prom;
 mod publisher;
+mod rate_limiter;
+mod reload;
+mod slot_tree;
+mod startup;
 mod system_programs;
+mod topic_template;
+mod unknown_keys;
+mod validation;
 mod version;
 
 pub use {
     cluster::Cluster,
+    commitment::Commitment,
     config::{Config, Producer},
     env_config::EnvConfig,
     errors::*,
     event::*,
-    filter::Filter,
+    filter::{EventPolicy, Filter, TransactionCategory, TransactionMatch},
     plugin::KafkaPlugin,
     prom::PrometheusService,
-    publisher::{serializable_events, FilteringPublisher},
+    publisher::{serializable_events, FilteringPublisher, OverflowPolicy},
     system_programs::*,
 };
 
@@ -0,0 +1,145 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::prom::OWNER_CACHE_LOOKUPS_TOTAL;
+
+/// Remembers the owners of recently updated accounts so that account creations and deletions
+/// can be published with the actual owner of the account.
+/// Once [capacity] accounts are cached, the account cached first is evicted.
+#[derive(Default)]
+pub struct OwnerCache {
+    capacity: usize,
+    state: Mutex<OwnerCacheState>,
+}
+
+/// Removed accounts are left in [insertion_order] and skipped on eviction, their entries are
+/// told apart from accounts cached again later by the generation they were inserted with.
+#[derive(Default)]
+struct OwnerCacheState {
+    owners: HashMap<Pubkey, (Pubkey, u64)>,
+    insertion_order: VecDeque<(Pubkey, u64)>,
+    generation: u64,
+}
+
+impl OwnerCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    pub fn insert(&self, account: &[u8], owner: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let (Ok(account), Ok(owner)) = (Pubkey::try_from(account), Pubkey::try_from(owner)) else {
+            return;
+        };
+
+        let mut state = self.state.lock().expect("owner cache mutex poisoned");
+        let state = &mut *state;
+        match state.owners.get_mut(&account) {
+            Some((cached, _)) => *cached = owner,
+            None => {
+                state.generation += 1;
+                state.owners.insert(account, (owner, state.generation));
+                state.insertion_order.push_back((account, state.generation));
+            }
+        }
+        // Also bounds the removed accounts left in the insertion order
+        while state.owners.len() > self.capacity || state.insertion_order.len() > 2 * self.capacity
+        {
+            let Some((evicted, generation)) = state.insertion_order.pop_front() else {
+                break;
+            };
+            if state.owners.get(&evicted).map(|(_, cached)| *cached) == Some(generation) {
+                state.owners.remove(&evicted);
+            }
+        }
+    }
+
+    /// Returns the owner of [account] if it is cached.
+    pub fn get(&self, account: &Pubkey) -> Option<Pubkey> {
+        let state = self.state.lock().expect("owner cache mutex poisoned");
+        let owner = state.owners.get(account).map(|(owner, _)| *owner);
+        Self::record_lookup(&owner);
+        owner
+    }
+
+    /// Removes and returns the owner of the deleted [account] if it is cached.
+    pub fn remove(&self, account: &Pubkey) -> Option<Pubkey> {
+        let mut state = self.state.lock().expect("owner cache mutex poisoned");
+        let owner = state.owners.remove(account).map(|(owner, _)| owner);
+        Self::record_lookup(&owner);
+        owner
+    }
+
+    fn record_lookup(owner: &Option<Pubkey>) {
+        OWNER_CACHE_LOOKUPS_TOTAL
+            .with_label_values(&[if owner.is_some() { "hit" } else { "miss" }])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_accounts_cached_first() {
+        let cache = OwnerCache::new(2);
+        let accounts = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let owner = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+
+        cache.insert(accounts[0].as_ref(), owner.as_ref());
+        cache.insert(accounts[1].as_ref(), owner.as_ref());
+        // Updating a cached account does not change its eviction order
+        cache.insert(accounts[0].as_ref(), new_owner.as_ref());
+        cache.insert(accounts[2].as_ref(), owner.as_ref());
+
+        assert_eq!(cache.remove(&accounts[0]), None);
+        assert_eq!(cache.get(&accounts[1]), Some(owner));
+        assert_eq!(cache.remove(&accounts[1]), Some(owner));
+        assert_eq!(cache.remove(&accounts[1]), None);
+        assert_eq!(cache.remove(&accounts[2]), Some(owner));
+    }
+
+    #[test]
+    fn skips_removed_accounts_on_eviction() {
+        let cache = OwnerCache::new(2);
+        let accounts = (0..3).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+        let owner = Pubkey::new_unique();
+
+        cache.insert(accounts[0].as_ref(), owner.as_ref());
+        cache.insert(accounts[1].as_ref(), owner.as_ref());
+        assert_eq!(cache.remove(&accounts[0]), Some(owner));
+        // Cached again after it was removed, thus evicted after the other accounts
+        cache.insert(accounts[0].as_ref(), owner.as_ref());
+        cache.insert(accounts[2].as_ref(), owner.as_ref());
+
+        assert_eq!(cache.get(&accounts[1]), None);
+        assert_eq!(cache.get(&accounts[0]), Some(owner));
+        assert_eq!(cache.get(&accounts[2]), Some(owner));
+
+        for _ in 0..10 {
+            let account = Pubkey::new_unique();
+            cache.insert(account.as_ref(), owner.as_ref());
+            cache.remove(&account);
+        }
+        assert!(cache.state.lock().unwrap().insertion_order.len() <= 4);
+    }
+
+    #[test]
+    fn disabled_without_capacity() {
+        let cache = OwnerCache::new(0);
+        let account = Pubkey::new_unique();
+        cache.insert(account.as_ref(), Pubkey::new_unique().as_ref());
+        assert_eq!(cache.remove(&account), None);
+    }
+}
@@ -12,26 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use crate::events::update_account_event::publish_deleted_account_events;
+use crate::events::{
+    account_created_event::publish_created_account_events,
+    replica_account_info::ReplicaAccountInfo, replica_transaction_info::ReplicaTransactionInfo,
+    update_account_event::publish_deleted_account_events,
+};
 
 use {
     crate::{
+        commitment::{BufferedEvent, CommitmentBuffer},
+        compaction::AccountCompactor,
         is_system_program,
-        publisher::{kafka_publisher::KafkaPublisher, LocalPublisher, Publisher},
-        sanitized_message, CompiledInstruction, Config, EnvConfig, Filter, FilteringPublisher,
-        InnerInstruction, InnerInstructions, LegacyLoadedMessage, LegacyMessage, LoadedAddresses,
-        MessageAddressTableLookup, MessageHeader, PrometheusService, Reward, SanitizedMessage,
-        SanitizedTransaction, SlotStatus, SlotStatusEvent, TransactionEvent, TransactionStatusMeta,
+        owner_cache::OwnerCache,
+        prom::{
+            REMOVED_ENV_DISCARDED_EVENTS_TOTAL, REPLICA_INFO_VERSIONS_TOTAL,
+            TRANSACTIONS_EXCLUDED_TOTAL, TRANSACTION_CATEGORIES_TOTAL,
+        },
+        publisher::{kafka_publisher::KafkaPublisher, LocalPublisher, Publisher, QueuedPublisher},
+        reload::{ConfigWatcher, EnvState, SharedPublishers},
+        sanitized_message,
+        slot_tree::SlotTree,
+        startup::StartupProgress,
+        AccountUpdateSource, BlockMetadataEvent, Commitment, CompiledInstruction, Config,
+        EntryEvent, EnvConfig, Filter, FilteringPublisher, InnerInstruction, InnerInstructions,
+        LegacyLoadedMessage, LegacyMessage, LoadedAddresses, MessageAddressTableLookup,
+        MessageHeader, PrometheusService, Reward, SanitizedMessage, SanitizedTransaction,
+        SlotStatus, SlotStatusEvent, TransactionCategory, TransactionEvent, TransactionStatusMeta,
         TransactionTokenBalance, UiTokenAmount, UpdateAccountEvent, V0LoadedMessage, V0Message,
     },
     log::{debug, info, log_enabled, trace},
     rdkafka::util::get_rdkafka_version,
+    simple_error::SimpleError,
     solana_geyser_plugin_interface::geyser_plugin_interface::{
-        GeyserPlugin, GeyserPluginError as PluginError, ReplicaAccountInfoV3,
-        ReplicaAccountInfoVersions, ReplicaTransactionInfoV2, ReplicaTransactionInfoVersions,
-        Result as PluginResult, SlotStatus as PluginSlotStatus,
+        GeyserPlugin, GeyserPluginError as PluginError, ReplicaAccountInfoVersions,
+        ReplicaBlockInfoVersions, ReplicaEntryInfoVersions, ReplicaTransactionInfoV2,
+        ReplicaTransactionInfoVersions, Result as PluginResult, SlotStatus as PluginSlotStatus,
     },
     solana_program::message::AccountKeys,
     solana_program::pubkey::Pubkey,
@@ -40,11 +62,21 @@ use {
 
 #[derive(Default)]
 pub struct KafkaPlugin {
-    publishers: Option<Vec<Publisher>>,
-    publish_all_accounts: bool,
-    publish_account_deletions: bool,
-    publish_accounts_without_signature: bool,
+    publishers: SharedPublishers,
+    /// Replaces the publishers once the config file changed if enabled.
+    config_watcher: Option<ConfigWatcher>,
     prometheus: Option<PrometheusService>,
+    startup_progress: Arc<StartupProgress>,
+    /// The owners of recently updated accounts, used to publish account deletions.
+    owner_cache: OwnerCache,
+
+    /// The parents of the slots processed since the last root.
+    slot_tree: Mutex<SlotTree>,
+    /// Account and transaction events waiting for their slot to reach the commitment level of
+    /// the environment they are destined for.
+    commitment_buffer: Arc<CommitmentBuffer>,
+    /// Coalesces the account updates within a slot for the environments that enable it.
+    account_compactor: Arc<AccountCompactor>,
 
     /// A global monotonically increasing atomic number, which can be used
     /// to tell the order of the account update. For example, when an
@@ -66,7 +98,12 @@ impl GeyserPlugin for KafkaPlugin {
     }
 
     fn on_load(&mut self, config_file: &str) -> PluginResult<()> {
-        if self.publishers.is_some() {
+        if self
+            .publishers
+            .read()
+            .expect("publishers lock poisoned")
+            .is_some()
+        {
             return Err(PluginError::Custom("plugin already loaded".into()));
         }
 
@@ -77,57 +114,47 @@ impl GeyserPlugin for KafkaPlugin {
             config_file
         );
         let config = Config::read_from(config_file)?;
-        self.publish_all_accounts = config.publish_all_accounts;
-        self.publish_account_deletions = config.publish_account_deletions;
-        self.publish_accounts_without_signature = config.publish_accounts_without_signature;
+        config
+            .validate_on_load()
+            .map_err(|errors| PluginError::ConfigFileReadError {
+                msg: errors.join(" | "),
+            })?;
+        self.owner_cache = OwnerCache::new(config.owner_cache_capacity);
 
         let (version_n, version_s) = get_rdkafka_version();
         info!("rd_kafka_version: {:#08x}, {}", version_n, version_s);
 
-        let mut publishers = Vec::new();
-        for env_config in &config.environments {
-            let filter = Filter::new(env_config);
-            match env_config {
-                EnvConfig::Kafka(env_config) => {
-                    let producer = env_config
-                        .producer()
-                        .map_err(|e| PluginError::Custom(Box::new(e)))?;
-                    info!("Created rdkafka::FutureProducer");
-
-                    let publisher =
-                        KafkaPublisher::new(producer, &config, env_config.name.to_string());
-                    let publisher =
-                        Publisher::FilteringPublisher(FilteringPublisher::new(publisher, filter));
-                    publishers.push(publisher);
-                }
-                EnvConfig::Local(env_config) => {
-                    let publisher = Publisher::LocalPublisher(LocalPublisher::new(
-                        filter,
-                        &config,
-                        env_config.name.to_string(),
-                        env_config.url.clone(),
-                        env_config.include_system_accounts,
-                    ));
-                    info!(
-                        "Created local http publisher '{}', publishing to '{}'",
-                        env_config.name, env_config.url
-                    );
-                    publishers.push(publisher);
-                }
-            }
-        }
+        self.startup_progress = Arc::new(StartupProgress::new(
+            config.environments.iter().map(EnvConfig::name),
+        ));
+        let publishers = Self::create_publishers(&config, &self.startup_progress)?;
         let prometheus = config
             .create_prometheus()
             .map_err(|error| PluginError::Custom(Box::new(error)))?;
-        self.publishers = Some(publishers);
+        *self.publishers.write().expect("publishers lock poisoned") = Some(Arc::new(publishers));
         self.prometheus = prometheus;
         info!("Spawned producers");
 
+        if config.reload_interval_secs > 0 {
+            self.config_watcher = Some(ConfigWatcher::new(
+                PathBuf::from(config_file),
+                &config,
+                self.publishers.clone(),
+                EnvState {
+                    startup_progress: self.startup_progress.clone(),
+                    commitment_buffer: self.commitment_buffer.clone(),
+                    account_compactor: self.account_compactor.clone(),
+                },
+                Duration::from_secs(config.reload_interval_secs),
+            ));
+        }
+
         Ok(())
     }
 
     fn on_unload(&mut self) {
-        self.publishers = None;
+        self.config_watcher = None;
+        *self.publishers.write().expect("publishers lock poisoned") = None;
         if let Some(prometheus) = self.prometheus.take() {
             prometheus.shutdown();
         }
@@ -139,19 +166,25 @@ impl GeyserPlugin for KafkaPlugin {
         slot: u64,
         is_startup: bool,
     ) -> PluginResult<()> {
-        if is_startup && !self.publish_all_accounts {
+        let publishers = self.unwrap_publishers();
+        let publishers = &publishers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+        if is_startup && !publishers.iter().any(|p| p.policy().publish_all_accounts) {
             return Ok(());
         }
 
-        let info = Self::unwrap_update_account(account);
-        if !self.publish_accounts_without_signature && info.txn.is_none() {
-            Self::log_ignore_account_update(info, "No Transaction Signature");
+        let info = ReplicaAccountInfo::from(account);
+        if !publishers.iter().any(|p| p.wants_account_key(info.owner)) {
+            Self::log_ignore_account_update(&info, "No publisher wants this account");
             return Ok(());
         }
+        self.owner_cache.insert(info.pubkey, info.owner);
 
-        let publishers = &self.unwrap_publishers();
-        if !publishers.iter().any(|p| p.wants_account_key(info.owner)) {
-            Self::log_ignore_account_update(info, "No publisher wants this account");
+        if info.txn_signature.is_none()
+            && !publishers
+                .iter()
+                .any(|p| p.policy().publish_accounts_without_signature)
+        {
+            Self::log_ignore_account_update(&info, "No Transaction Signature");
             return Ok(());
         }
 
@@ -164,7 +197,12 @@ impl GeyserPlugin for KafkaPlugin {
             rent_epoch: info.rent_epoch,
             data: info.data.to_vec(),
             write_version: info.write_version,
-            txn_signature: info.txn.map(|v| v.signature().as_ref().to_owned()),
+            txn_signature: info.txn_signature.map(|v| v.as_ref().to_owned()),
+            is_startup,
+            source: AccountUpdateSource::Validator.into(),
+            is_deleted: info.lamports == 0,
+            transaction_index: None,
+            account_index: None,
         };
         *self
             .last_published_write_version
@@ -172,19 +210,47 @@ impl GeyserPlugin for KafkaPlugin {
             .expect("write_version mutex poisoned") = event.write_version;
 
         let mut errors = Vec::new();
-        for publisher in publishers {
-            if !publisher.wants_account_key(info.owner) {
+        self.publish_account(publishers, event, &mut errors);
+        if !errors.is_empty() {
+            Err(PluginError::AccountsUpdateError {
+                msg: errors.join(" | "),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn notify_end_of_startup(&self) -> PluginResult<()> {
+        self.startup_progress.complete();
+
+        let mut errors = Vec::new();
+        for publisher in self.unwrap_publishers().iter() {
+            if !publisher.wants_end_of_startup() || !publisher.policy().publish_all_accounts {
                 continue;
             }
 
-            if let Err(err) = publisher.update_account(event.clone()) {
+            let event = self.startup_progress.end_of_startup_event(publisher.env());
+            if let Publisher::QueuedPublisher(_) = **publisher {
+                info!(
+                    "Queueing end of startup for {} environment",
+                    publisher.env()
+                );
+            } else {
+                info!(
+                    "Publishing end of startup to {} environment after {} accounts",
+                    publisher.env(),
+                    event.total_accounts
+                );
+            }
+            if let Err(err) = publisher.update_end_of_startup(event) {
                 errors.push(format!("Error: {} in {} environment", err, publisher.env()));
             }
         }
         if !errors.is_empty() {
-            Err(PluginError::AccountsUpdateError {
-                msg: errors.join(" | "),
-            })
+            Err(PluginError::Custom(Box::new(SimpleError::new(format!(
+                "Error publishing end of startup. Error message: ({})",
+                errors.join(" | ")
+            )))))
         } else {
             Ok(())
         }
@@ -196,16 +262,68 @@ impl GeyserPlugin for KafkaPlugin {
         parent: Option<u64>,
         status: PluginSlotStatus,
     ) -> PluginResult<()> {
-        let publishers = &self.unwrap_publishers();
+        let publishers = self.unwrap_publishers();
+        let publishers = &publishers.iter().map(Arc::as_ref).collect::<Vec<_>>();
         if let PluginSlotStatus::Confirmed = status {
             for publisher in publishers {
                 publisher
                     .get_allowlist()
                     .update_from_http_if_needed_async(slot);
+                publisher
+                    .get_denylist()
+                    .update_from_http_if_needed_async(slot);
             }
         };
 
         let mut errors = Vec::new();
+
+        for (env, event) in self.account_compactor.flush(slot) {
+            let Some(publisher) = Self::publisher_of(publishers, &env) else {
+                // Buffered right before a reload removed the environment
+                REMOVED_ENV_DISCARDED_EVENTS_TOTAL
+                    .with_label_values(&[&env])
+                    .inc();
+                continue;
+            };
+            let result =
+                self.publish_or_buffer(publisher, event.slot, BufferedEvent::Account(event));
+            if let Err(err) = result {
+                errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+            }
+        }
+
+        let (parent, events, dead_slots) = {
+            let mut slot_tree = self.slot_tree.lock().expect("slot tree mutex poisoned");
+            if let Some(parent) = parent {
+                slot_tree.insert(slot, parent);
+            }
+            let parent = parent.or_else(|| slot_tree.parent(slot));
+            let events = self
+                .commitment_buffer
+                .flush(slot, Commitment::from(status), &slot_tree);
+            let dead_slots = match status {
+                PluginSlotStatus::Rooted => slot_tree.root(slot),
+                _ => vec![],
+            };
+            (parent, events, dead_slots)
+        };
+        if !dead_slots.is_empty() {
+            let slots = dead_slots.iter().map(|(slot, _)| *slot).collect::<Vec<_>>();
+            self.commitment_buffer.discard(&slots);
+        }
+        for (env, event) in events {
+            let Some(publisher) = Self::publisher_of(publishers, &env) else {
+                // Buffered right before a reload removed the environment
+                REMOVED_ENV_DISCARDED_EVENTS_TOTAL
+                    .with_label_values(&[&env])
+                    .inc();
+                continue;
+            };
+            if let Err(err) = Self::publish(publisher, event) {
+                errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+            }
+        }
+
         for publisher in publishers {
             if !publisher.wants_slot_status() {
                 continue;
@@ -221,6 +339,7 @@ impl GeyserPlugin for KafkaPlugin {
                 errors.push(format!("Error: {} in {} environment", err, publisher.env()));
             }
         }
+        Self::publish_dead_slots(publishers, &dead_slots, &mut errors);
 
         if !errors.is_empty() {
             Err(PluginError::SlotStatusUpdateError {
@@ -237,45 +356,90 @@ impl GeyserPlugin for KafkaPlugin {
         slot: u64,
     ) -> PluginResult<()> {
         let publishers = self.unwrap_publishers();
-        let info = Self::unwrap_transaction(transaction);
+        let publishers = publishers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+        let (info, index) = Self::unwrap_transaction(transaction);
+        let transaction_info = ReplicaTransactionInfo::new(&info, slot, index);
 
         let mut errors = Vec::new();
 
         // We do not get account updates when an account is deleted, therefore we extract
         // those events from the transactions instead.
+        let deletion_publishers = publishers
+            .iter()
+            .filter(|p| p.policy().publish_account_deletions)
+            .copied()
+            .collect::<Vec<_>>();
         let account_errors = publish_deleted_account_events(
-            &publishers,
-            info,
-            slot,
+            &deletion_publishers,
+            &transaction_info,
             &self.last_published_write_version,
+            &self.owner_cache,
+            |publisher, event| {
+                self.publish_or_buffer(publisher, slot, BufferedEvent::Account(event))
+            },
         );
         for account_error in account_errors {
             errors.push(account_error.to_string());
         }
 
+        // Neither do we get a dedicated notification when an account is created.
+        let created_errors = publish_created_account_events(
+            &publishers,
+            &transaction_info,
+            &self.owner_cache,
+            |publisher, event| {
+                self.publish_or_buffer(publisher, slot, BufferedEvent::AccountCreated(event))
+            },
+        );
+        for created_error in created_errors {
+            errors.push(created_error.to_string());
+        }
+
+        let account_keys = info.transaction.message().account_keys();
+        let mut matched_keys = HashMap::new();
+        let mut event = None;
+        let category =
+            TransactionCategory::new(info.is_vote, info.transaction_status_meta.status.is_err());
+        TRANSACTION_CATEGORIES_TOTAL
+            .with_label_values(&[category.as_str()])
+            .inc();
         for publisher in publishers {
-            if !publisher.wants_transaction() {
+            if !publisher.wants_transaction_of(category) {
                 continue;
             }
 
-            let account_keys = info.transaction.message().account_keys();
-            let wanted = account_keys.iter().any(|key| {
-                self.unwrap_publishers()
-                    .iter()
-                    .any(|p| p.wants_account_key(&key.to_bytes()))
-            });
-            if !wanted {
+            if !publisher.wants_transaction_category(category) {
+                TRANSACTIONS_EXCLUDED_TOTAL
+                    .with_label_values(&[publisher.env(), category.as_str()])
+                    .inc();
+                continue;
+            }
+
+            let transaction_match = publisher.transaction_match();
+            let keys = matched_keys
+                .entry(transaction_match)
+                .or_insert_with(|| transaction_info.matched_keys(transaction_match));
+            if !keys
+                .iter()
+                .any(|key| publisher.wants_account_key(key.as_ref()))
+            {
                 Self::log_ignore_transaction_update(
-                    info,
+                    &info,
                     &account_keys,
-                    "None of the accounts are wanted",
+                    &format!(
+                        "None of the accounts are wanted in {} environment",
+                        publisher.env()
+                    ),
                 );
-                return Ok(());
+                continue;
             }
 
-            let event = Self::build_transaction_event(slot, info);
-
-            if let Err(err) = publisher.update_transaction(event) {
+            let event = event
+                .get_or_insert_with(|| Self::build_transaction_event(&transaction_info))
+                .clone();
+            if let Err(err) =
+                self.publish_or_buffer(publisher, slot, BufferedEvent::Transaction(Box::new(event)))
+            {
                 errors.push(format!("Error: {} in {} environment", err, publisher.env()));
             }
         }
@@ -288,6 +452,68 @@ impl GeyserPlugin for KafkaPlugin {
         }
     }
 
+    fn notify_block_metadata(&self, blockinfo: ReplicaBlockInfoVersions) -> PluginResult<()> {
+        let publishers = self.unwrap_publishers();
+        let publishers = publishers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+        if !publishers.iter().any(|p| p.wants_block_metadata()) {
+            return Ok(());
+        }
+
+        let event = Self::build_block_metadata_event(blockinfo);
+
+        let mut errors = Vec::new();
+        for publisher in publishers {
+            if !publisher.wants_block_metadata() {
+                continue;
+            }
+
+            if let Err(err) = publisher.update_block_metadata(event.clone()) {
+                errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+            }
+        }
+        if !errors.is_empty() {
+            Err(PluginError::Custom(Box::new(SimpleError::new(format!(
+                "Error updating block metadata. Error message: ({})",
+                errors.join(" | ")
+            )))))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn notify_entry(&self, entry: ReplicaEntryInfoVersions) -> PluginResult<()> {
+        let publishers = self.unwrap_publishers();
+        let publishers = publishers.iter().map(Arc::as_ref).collect::<Vec<_>>();
+        let event = match entry {
+            ReplicaEntryInfoVersions::V0_0_1(info) => EntryEvent {
+                slot: info.slot,
+                index: info.index as u64,
+                num_hashes: info.num_hashes,
+                hash: info.hash.to_vec(),
+                executed_transaction_count: info.executed_transaction_count,
+            },
+        };
+
+        let mut errors = Vec::new();
+        for publisher in publishers {
+            if !publisher.wants_entry() {
+                continue;
+            }
+
+            if let Err(err) = publisher.update_entry(event.clone()) {
+                errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+            }
+        }
+        if !errors.is_empty() {
+            Err(PluginError::Custom(Box::new(SimpleError::new(format!(
+                "Error updating entry. Error message: ({})",
+                errors.join(" | ")
+            )))))
+        } else {
+            Ok(())
+        }
+    }
+
     fn account_data_notifications_enabled(&self) -> bool {
         self.unwrap_publishers()
             .iter()
@@ -295,10 +521,15 @@ impl GeyserPlugin for KafkaPlugin {
     }
 
     fn transaction_notifications_enabled(&self) -> bool {
-        self.unwrap_publishers()
-            .iter()
-            .any(|p| p.wants_transaction())
-            || (self.publish_account_deletions && self.account_data_notifications_enabled())
+        self.unwrap_publishers().iter().any(|p| {
+            p.wants_transaction()
+                || p.wants_account_created()
+                || (p.policy().publish_account_deletions && p.wants_update_account())
+        })
+    }
+
+    fn entry_notifications_enabled(&self) -> bool {
+        self.unwrap_publishers().iter().any(|p| p.wants_entry())
     }
 }
 
@@ -307,37 +538,217 @@ impl KafkaPlugin {
         Default::default()
     }
 
-    fn unwrap_publishers(&self) -> Vec<&Publisher> {
+    /// Creates the publishers of all environments of [config].
+    pub(crate) fn create_publishers(
+        config: &Config,
+        startup_progress: &Arc<StartupProgress>,
+    ) -> PluginResult<Vec<Arc<Publisher>>> {
+        config
+            .environments
+            .iter()
+            .map(|env_config| {
+                Self::create_publisher(config, env_config, startup_progress).map(Arc::new)
+            })
+            .collect()
+    }
+
+    /// Creates the publisher of the environment [env_config] of [config].
+    pub(crate) fn create_publisher(
+        config: &Config,
+        env_config: &EnvConfig,
+        startup_progress: &Arc<StartupProgress>,
+    ) -> PluginResult<Publisher> {
+        let filter = Filter::new(env_config, config)?;
+        let publisher = match env_config {
+            EnvConfig::Kafka(env_config) => {
+                let producer = env_config
+                    .producer()
+                    .map_err(|e| PluginError::Custom(Box::new(e)))?;
+                info!("Created rdkafka::FutureProducer");
+
+                let publisher = KafkaPublisher::new(producer, config, env_config);
+                Publisher::FilteringPublisher(FilteringPublisher::new(publisher, filter))
+            }
+            EnvConfig::Local(env_config) => {
+                let publisher =
+                    Publisher::LocalPublisher(LocalPublisher::new(filter, config, env_config));
+                info!(
+                    "Created local http publisher '{}', publishing to '{}'",
+                    env_config.name, env_config.url
+                );
+                publisher
+            }
+        };
+
+        let queue = env_config.queue();
+        if queue.capacity > 0 {
+            info!(
+                "Publishing to '{}' via a queue of {} events",
+                publisher.env(),
+                queue.capacity
+            );
+            Ok(Publisher::QueuedPublisher(QueuedPublisher::new(
+                publisher,
+                queue,
+                startup_progress.clone(),
+            )))
+        } else {
+            Ok(publisher)
+        }
+    }
+
+    fn unwrap_publishers(&self) -> Arc<Vec<Arc<Publisher>>> {
         self.publishers
-            .as_ref()
+            .read()
+            .expect("publishers lock poisoned")
+            .clone()
             .expect("filtered publishers are unavailable")
-            .iter()
-            .collect::<Vec<_>>()
     }
 
-    fn unwrap_update_account(account: ReplicaAccountInfoVersions) -> &ReplicaAccountInfoV3 {
-        match account {
-            ReplicaAccountInfoVersions::V0_0_1(_info) => {
-                panic!("ReplicaAccountInfoVersions::V0_0_1 unsupported, please upgrade your Solana node.");
+    /// Returns the publisher of [env], if the environment was not removed by a reload.
+    fn publisher_of<'a>(publishers: &[&'a Publisher], env: &str) -> Option<&'a Publisher> {
+        publishers.iter().find(|p| p.env() == env).copied()
+    }
+
+    /// Publishes the account update [event] to all publishers that want it.
+    fn publish_account(
+        &self,
+        publishers: &[&Publisher],
+        event: UpdateAccountEvent,
+        errors: &mut Vec<String>,
+    ) {
+        for publisher in publishers {
+            if !publisher.wants_account_key(&event.owner) {
+                continue;
+            }
+            let policy = publisher.policy();
+            if (event.is_startup && !policy.publish_all_accounts)
+                || (event.txn_signature.is_none() && !policy.publish_accounts_without_signature)
+            {
+                continue;
+            }
+
+            // Updates are compacted after filtering so that each environment gets the latest
+            // of the updates it wants
+            if policy.compact_account_updates && !event.is_startup {
+                for event in self.account_compactor.push(publisher.env(), event.clone()) {
+                    if let Err(err) =
+                        self.publish_or_buffer(publisher, event.slot, BufferedEvent::Account(event))
+                    {
+                        errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+                    }
+                }
+                continue;
             }
-            ReplicaAccountInfoVersions::V0_0_2(_info) => {
-                panic!("ReplicaAccountInfoVersions::V0_0_2 unsupported, please upgrade your Solana node.");
+
+            // Accounts streamed on startup are rooted already
+            let result = if event.is_startup {
+                publisher.update_account(event.clone())
+            } else {
+                self.publish_or_buffer(publisher, event.slot, BufferedEvent::Account(event.clone()))
+            };
+            match result {
+                // Queued startup accounts are recorded once they are published
+                Ok(())
+                    if event.is_startup && !matches!(publisher, Publisher::QueuedPublisher(_)) =>
+                {
+                    self.startup_progress
+                        .record(publisher.env(), &event.owner, event.slot)
+                }
+                Ok(()) => {}
+                Err(err) => {
+                    errors.push(format!("Error: {} in {} environment", err, publisher.env()))
+                }
             }
-            ReplicaAccountInfoVersions::V0_0_3(info) => info,
         }
     }
 
-    fn unwrap_transaction(
-        transaction: ReplicaTransactionInfoVersions,
-    ) -> &ReplicaTransactionInfoV2 {
-        match transaction {
-            ReplicaTransactionInfoVersions::V0_0_1(_info) => {
-                panic!("ReplicaTransactionInfoVersions::V0_0_1 unsupported, please upgrade your Solana node.");
+    /// Publishes [event] right away or buffers it until [slot] reaches the commitment level
+    /// of the [publisher].
+    fn publish_or_buffer(
+        &self,
+        publisher: &Publisher,
+        slot: u64,
+        event: BufferedEvent,
+    ) -> crate::PluginResult<()> {
+        match publisher.commitment() {
+            Commitment::Processed => Self::publish(publisher, event),
+            commitment => {
+                self.commitment_buffer
+                    .push(slot, commitment, publisher.env(), event);
+                Ok(())
+            }
+        }
+    }
+
+    fn publish(publisher: &Publisher, event: BufferedEvent) -> crate::PluginResult<()> {
+        match event {
+            BufferedEvent::Account(ev) => publisher.update_account(ev),
+            BufferedEvent::Transaction(ev) => publisher.update_transaction(*ev),
+            BufferedEvent::AccountCreated(ev) => publisher.update_account_created(ev),
+        }
+    }
+
+    /// Publishes a [SlotStatus::Dead] event for each of [dead_slots] so that consumers can
+    /// roll back state derived from abandoned forks.
+    fn publish_dead_slots(
+        publishers: &[&Publisher],
+        dead_slots: &[(u64, u64)],
+        errors: &mut Vec<String>,
+    ) {
+        for publisher in publishers {
+            if !publisher.wants_slot_status() {
+                continue;
+            }
+            for (slot, parent) in dead_slots {
+                let event = SlotStatusEvent {
+                    slot: *slot,
+                    parent: *parent,
+                    status: SlotStatus::Dead.into(),
+                };
+                if let Err(err) = publisher.update_slot_status(event) {
+                    errors.push(format!("Error: {} in {} environment", err, publisher.env()));
+                }
             }
-            ReplicaTransactionInfoVersions::V0_0_2(info) => info,
         }
     }
 
+    /// Returns the transaction along with its index in the block, which is not provided by
+    /// [ReplicaTransactionInfoVersions::V0_0_1] and thus `None` for it.
+    fn unwrap_transaction(
+        transaction: ReplicaTransactionInfoVersions,
+    ) -> (ReplicaTransactionInfoV2, Option<usize>) {
+        let (version, info, index) = match transaction {
+            // The index field is required by ReplicaTransactionInfoV2, but must not be used
+            ReplicaTransactionInfoVersions::V0_0_1(info) => (
+                "0.0.1",
+                ReplicaTransactionInfoV2 {
+                    signature: info.signature,
+                    is_vote: info.is_vote,
+                    transaction: info.transaction,
+                    transaction_status_meta: info.transaction_status_meta,
+                    index: 0,
+                },
+                None,
+            ),
+            ReplicaTransactionInfoVersions::V0_0_2(info) => (
+                "0.0.2",
+                ReplicaTransactionInfoV2 {
+                    signature: info.signature,
+                    is_vote: info.is_vote,
+                    transaction: info.transaction,
+                    transaction_status_meta: info.transaction_status_meta,
+                    index: info.index,
+                },
+                Some(info.index),
+            ),
+        };
+        REPLICA_INFO_VERSIONS_TOTAL
+            .with_label_values(&["transaction", version])
+            .inc();
+        (info, index)
+    }
+
     fn build_compiled_instruction(
         ix: &solana_program::instruction::CompiledInstruction,
     ) -> CompiledInstruction {
@@ -383,20 +794,80 @@ impl KafkaPlugin {
         }
     }
 
-    fn build_transaction_event(
-        slot: u64,
-        ReplicaTransactionInfoV2 {
+    fn build_reward(reward: &solana_transaction_status::Reward) -> Reward {
+        Reward {
+            pubkey: reward.pubkey.clone(),
+            lamports: reward.lamports,
+            post_balance: reward.post_balance,
+            reward_type: match reward.reward_type {
+                Some(r) => r as i32,
+                None => 0,
+            },
+            commission: match reward.commission {
+                Some(v) => v as u32,
+                None => 0,
+            },
+        }
+    }
+
+    fn build_block_metadata_event(blockinfo: ReplicaBlockInfoVersions) -> BlockMetadataEvent {
+        let version = match blockinfo {
+            ReplicaBlockInfoVersions::V0_0_1(_) => "0.0.1",
+            ReplicaBlockInfoVersions::V0_0_2(_) => "0.0.2",
+            ReplicaBlockInfoVersions::V0_0_3(_) => "0.0.3",
+        };
+        REPLICA_INFO_VERSIONS_TOTAL
+            .with_label_values(&["block", version])
+            .inc();
+
+        match blockinfo {
+            ReplicaBlockInfoVersions::V0_0_1(info) => BlockMetadataEvent {
+                slot: info.slot,
+                blockhash: info.blockhash.to_string(),
+                rewards: info.rewards.iter().map(Self::build_reward).collect(),
+                block_time: info.block_time,
+                block_height: info.block_height,
+                ..Default::default()
+            },
+            ReplicaBlockInfoVersions::V0_0_2(info) => BlockMetadataEvent {
+                slot: info.slot,
+                blockhash: info.blockhash.to_string(),
+                rewards: info.rewards.iter().map(Self::build_reward).collect(),
+                block_time: info.block_time,
+                block_height: info.block_height,
+                parent_slot: info.parent_slot,
+                parent_blockhash: info.parent_blockhash.to_string(),
+                executed_transaction_count: info.executed_transaction_count,
+                ..Default::default()
+            },
+            ReplicaBlockInfoVersions::V0_0_3(info) => BlockMetadataEvent {
+                slot: info.slot,
+                blockhash: info.blockhash.to_string(),
+                rewards: info.rewards.iter().map(Self::build_reward).collect(),
+                block_time: info.block_time,
+                block_height: info.block_height,
+                parent_slot: info.parent_slot,
+                parent_blockhash: info.parent_blockhash.to_string(),
+                executed_transaction_count: info.executed_transaction_count,
+                entry_count: info.entry_count,
+            },
+        }
+    }
+
+    fn build_transaction_event(transaction_info: &ReplicaTransactionInfo) -> TransactionEvent {
+        let ReplicaTransactionInfoV2 {
             signature,
             is_vote,
             transaction,
             transaction_status_meta,
-            index,
-        }: &ReplicaTransactionInfoV2,
-    ) -> TransactionEvent {
+            ..
+        } = &**transaction_info;
         TransactionEvent {
             is_vote: *is_vote,
-            slot,
-            index: *index as u64,
+            slot: transaction_info.slot(),
+            index: transaction_info
+                .transaction_index()
+                .map(|index| index as u64),
             signature: signature.as_ref().into(),
             transaction_status_meta: Some(TransactionStatusMeta {
                 is_status_err: transaction_status_meta.status.is_err(),
@@ -406,22 +877,10 @@ impl KafkaPlugin {
                 },
                 rewards: transaction_status_meta
                     .rewards
-                    .clone()
+                    .as_ref()
                     .unwrap()
-                    .into_iter()
-                    .map(|x| Reward {
-                        pubkey: x.pubkey,
-                        lamports: x.lamports,
-                        post_balance: x.post_balance,
-                        reward_type: match x.reward_type {
-                            Some(r) => r as i32,
-                            None => 0,
-                        },
-                        commission: match x.commission {
-                            Some(v) => v as u32,
-                            None => 0,
-                        },
-                    })
+                    .iter()
+                    .map(Self::build_reward)
                     .collect(),
                 fee: transaction_status_meta.fee,
                 log_messages: match &transaction_status_meta.log_messages {
@@ -562,7 +1021,7 @@ impl KafkaPlugin {
         }
     }
 
-    fn log_ignore_account_update(info: &ReplicaAccountInfoV3, reason: &str) {
+    fn log_ignore_account_update(info: &ReplicaAccountInfo, reason: &str) {
         let debug_ignored_updates = std::env::var("GEYSER_DEBUG_IGNORED_UPDATES").is_ok();
         if (debug_ignored_updates && log_enabled!(::log::Level::Debug))
             || log_enabled!(::log::Level::Trace)
@@ -619,3 +1078,64 @@ impl KafkaPlugin {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use solana_geyser_plugin_interface::geyser_plugin_interface::ReplicaTransactionInfo as ReplicaTransactionInfoV1;
+    use solana_program::{hash::Hash, message::Message};
+    use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+
+    use super::*;
+    use crate::AccountCreatedEvent;
+
+    #[test]
+    fn transaction_index_is_unset_for_v0_0_1() {
+        let payer = Keypair::new();
+        let transaction = solana_sdk::transaction::SanitizedTransaction::from_transaction_for_tests(
+            Transaction::new(
+                &[&payer],
+                Message::new(&[], Some(&payer.pubkey())),
+                Hash::default(),
+            ),
+        );
+        let transaction_status_meta = solana_transaction_status::TransactionStatusMeta {
+            pre_balances: vec![0],
+            post_balances: vec![10],
+            rewards: Some(vec![]),
+            ..Default::default()
+        };
+        let info = ReplicaTransactionInfoV1 {
+            signature: transaction.signature(),
+            is_vote: false,
+            transaction: &transaction,
+            transaction_status_meta: &transaction_status_meta,
+        };
+
+        let (info, index) =
+            KafkaPlugin::unwrap_transaction(ReplicaTransactionInfoVersions::V0_0_1(&info));
+        assert_eq!(index, None);
+        let transaction_info = ReplicaTransactionInfo::new(&info, 5, index);
+        let event = KafkaPlugin::build_transaction_event(&transaction_info);
+        assert_eq!(event.slot, 5);
+        assert_eq!(event.index, None);
+
+        // Neither is it set for the account events derived from the transaction
+        let created = AccountCreatedEvent::new(0, payer.pubkey(), None, &transaction_info);
+        assert_eq!(created.transaction_index, None);
+        let deleted = UpdateAccountEvent::for_account_deletion(
+            0,
+            payer.pubkey(),
+            vec![],
+            &transaction_info,
+            1,
+        );
+        assert_eq!(deleted.transaction_index, None);
+
+        let info = ReplicaTransactionInfoV2 { index: 3, ..info };
+        let (info, index) =
+            KafkaPlugin::unwrap_transaction(ReplicaTransactionInfoVersions::V0_0_2(&info));
+        let event =
+            KafkaPlugin::build_transaction_event(&ReplicaTransactionInfo::new(&info, 5, index));
+        assert_eq!(event.index, Some(3));
+    }
+}
@@ -6,7 +6,9 @@ use {
         Body, Request, Response, Server, StatusCode,
     },
     log::*,
-    prometheus::{GaugeVec, IntCounterVec, Opts, Registry, TextEncoder},
+    prometheus::{
+        GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+    },
     rdkafka::{
         client::ClientContext,
         producer::{DeliveryResult, ProducerContext},
@@ -39,6 +41,112 @@ lazy_static::lazy_static! {
         &["status"]
     ).unwrap();
 
+    pub static ref UPLOAD_BLOCK_METADATA_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("upload_block_metadata_total", "Status of uploaded block metadata"),
+        &["status"]
+    ).unwrap();
+
+    pub static ref UPLOAD_ENTRIES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("upload_entries_total", "Status of uploaded entries"),
+        &["status"]
+    ).unwrap();
+
+    pub static ref UPLOAD_END_OF_STARTUP_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("upload_end_of_startup_total", "Status of uploaded end of startup markers"),
+        &["status"]
+    ).unwrap();
+
+    pub static ref UPLOAD_ACCOUNTS_CREATED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("upload_accounts_created_total", "Status of uploaded account creations"),
+        &["status"]
+    ).unwrap();
+
+    pub static ref TRANSACTION_CATEGORIES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("transaction_categories_total", "Number of transactions received per category"),
+        &["category"]
+    ).unwrap();
+
+    pub static ref TRANSACTIONS_EXCLUDED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("transactions_excluded_total", "Number of transactions excluded per environment and category"),
+        &["env", "category"]
+    ).unwrap();
+
+    pub static ref OWNER_CACHE_LOOKUPS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("owner_cache_lookups_total", "Number of owner lookups for created and deleted accounts by result"),
+        &["result"]
+    ).unwrap();
+
+    pub static ref REPLICA_INFO_VERSIONS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("replica_info_versions_total", "Number of notifications received per kind and interface version"),
+        &["kind", "version"]
+    ).unwrap();
+
+    pub static ref STARTUP_ACCOUNTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("startup_accounts", "Number of startup accounts published per environment"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref STARTUP_COMPLETE: IntGauge = IntGauge::new(
+        "startup_complete", "Whether the validator finished streaming accounts on startup"
+    ).unwrap();
+
+    pub static ref STARTUP_ACCOUNT_BYTES: IntCounterVec = IntCounterVec::new(
+        Opts::new("startup_account_bytes", "Number of bytes of startup accounts sent per environment"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref STARTUP_THROTTLED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("startup_throttled_total", "Number of times publishing startup accounts was throttled per environment"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref STARTUP_QUEUE_FULL_RETRIES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("startup_queue_full_retries_total", "Number of times a startup account was retried since the producer queue was full"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref PUBLISH_QUEUE_DEPTH: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("publish_queue_depth", "Number of events waiting to be published per environment"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref PUBLISH_QUEUE_DROPPED_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("publish_queue_dropped_total", "Number of events dropped since the publish queue of an environment was full or closed"),
+        &["env", "policy"]
+    ).unwrap();
+
+    pub static ref COMMITMENT_BUFFERED_EVENTS: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("commitment_buffered_events", "Number of events waiting for their slot to reach a commitment level"),
+        &["commitment"]
+    ).unwrap();
+
+    pub static ref ACCOUNT_UPDATES_COALESCED_TOTAL: IntCounter = IntCounter::new(
+        "account_updates_coalesced_total", "Number of account updates superseded by a later update of the same account within a slot"
+    ).unwrap();
+
+    pub static ref COMMITMENT_DISCARDED_EVENTS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("commitment_discarded_events_total", "Number of buffered events discarded since their slot never reached a commitment level"),
+        &["commitment"]
+    ).unwrap();
+
+    pub static ref REMOVED_ENV_DISCARDED_EVENTS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("removed_env_discarded_events_total", "Number of buffered events discarded since their environment was removed by a config reload"),
+        &["env"]
+    ).unwrap();
+
+    pub static ref CONFIG_RELOADS_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("config_reloads_total", "Number of config reloads by status"),
+        &["status"]
+    ).unwrap();
+
+    pub static ref CONFIG_LAST_RELOAD_SUCCESS: IntGauge = IntGauge::new(
+        "config_last_reload_success", "Whether the last config reload succeeded"
+    ).unwrap();
+
+    pub static ref CONFIG_LAST_RELOAD_TIMESTAMP_SECONDS: IntGauge = IntGauge::new(
+        "config_last_reload_timestamp_seconds", "Unix time of the last config reload"
+    ).unwrap();
+
     static ref KAFKA_STATS: GaugeVec = GaugeVec::new(
         Opts::new("kafka_stats", "librdkafka metrics"),
         &["broker", "metric"]
@@ -65,6 +173,28 @@ impl PrometheusService {
             register!(UPLOAD_ACCOUNTS_TOTAL);
             register!(UPLOAD_SLOTS_TOTAL);
             register!(UPLOAD_TRANSACTIONS_TOTAL);
+            register!(UPLOAD_BLOCK_METADATA_TOTAL);
+            register!(UPLOAD_ENTRIES_TOTAL);
+            register!(UPLOAD_END_OF_STARTUP_TOTAL);
+            register!(UPLOAD_ACCOUNTS_CREATED_TOTAL);
+            register!(TRANSACTION_CATEGORIES_TOTAL);
+            register!(TRANSACTIONS_EXCLUDED_TOTAL);
+            register!(OWNER_CACHE_LOOKUPS_TOTAL);
+            register!(REPLICA_INFO_VERSIONS_TOTAL);
+            register!(STARTUP_ACCOUNTS);
+            register!(STARTUP_COMPLETE);
+            register!(STARTUP_ACCOUNT_BYTES);
+            register!(STARTUP_THROTTLED_TOTAL);
+            register!(STARTUP_QUEUE_FULL_RETRIES_TOTAL);
+            register!(PUBLISH_QUEUE_DEPTH);
+            register!(CONFIG_RELOADS_TOTAL);
+            register!(CONFIG_LAST_RELOAD_SUCCESS);
+            register!(CONFIG_LAST_RELOAD_TIMESTAMP_SECONDS);
+            register!(REMOVED_ENV_DISCARDED_EVENTS_TOTAL);
+            register!(PUBLISH_QUEUE_DROPPED_TOTAL);
+            register!(COMMITMENT_BUFFERED_EVENTS);
+            register!(COMMITMENT_DISCARDED_EVENTS_TOTAL);
+            register!(ACCOUNT_UPDATES_COALESCED_TOTAL);
             register!(KAFKA_STATS);
 
             for (key, value) in &[
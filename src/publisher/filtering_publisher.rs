@@ -1,6 +1,10 @@
 use rdkafka::error::KafkaError;
 
-use crate::{allowlist::Allowlist, Filter, SlotStatusEvent, TransactionEvent, UpdateAccountEvent};
+use crate::{
+    allowlist::Allowlist, AccountCreatedEvent, BlockMetadataEvent, Commitment, EndOfStartupEvent,
+    EntryEvent, EventPolicy, Filter, SlotStatusEvent, TransactionCategory, TransactionEvent,
+    TransactionMatch, UpdateAccountEvent,
+};
 
 use super::kafka_publisher::KafkaPublisher;
 
@@ -21,8 +25,28 @@ impl FilteringPublisher {
         self.filter.get_allowlist()
     }
 
+    pub fn get_denylist(&self) -> Allowlist {
+        self.filter.get_denylist()
+    }
+
     pub fn wants_account_key(&self, account_key: &[u8]) -> bool {
-        self.filter.wants_account_key(account_key, false)
+        self.filter.wants_account_key(account_key)
+    }
+
+    pub fn policy(&self) -> EventPolicy {
+        self.filter.policy()
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.filter.commitment()
+    }
+
+    pub fn transaction_match(&self) -> TransactionMatch {
+        self.filter.transaction_match()
+    }
+
+    pub fn wants_transaction_category(&self, category: TransactionCategory) -> bool {
+        self.filter.wants_transaction_category(category)
     }
 
     // -----------------
@@ -44,6 +68,26 @@ impl FilteringPublisher {
         self.publisher.wants_transaction()
     }
 
+    pub fn wants_transaction_of(&self, category: TransactionCategory) -> bool {
+        self.publisher.wants_transaction_of(category)
+    }
+
+    pub fn wants_block_metadata(&self) -> bool {
+        self.publisher.wants_block_metadata()
+    }
+
+    pub fn wants_entry(&self) -> bool {
+        self.publisher.wants_entry()
+    }
+
+    pub fn wants_end_of_startup(&self) -> bool {
+        self.publisher.wants_end_of_startup()
+    }
+
+    pub fn wants_account_created(&self) -> bool {
+        self.publisher.wants_account_created()
+    }
+
     pub fn update_account(&self, ev: UpdateAccountEvent) -> Result<(), KafkaError> {
         self.publisher.update_account(ev)
     }
@@ -55,4 +99,20 @@ impl FilteringPublisher {
     pub fn update_transaction(&self, ev: TransactionEvent) -> Result<(), KafkaError> {
         self.publisher.update_transaction(ev)
     }
+
+    pub fn update_block_metadata(&self, ev: BlockMetadataEvent) -> Result<(), KafkaError> {
+        self.publisher.update_block_metadata(ev)
+    }
+
+    pub fn update_entry(&self, ev: EntryEvent) -> Result<(), KafkaError> {
+        self.publisher.update_entry(ev)
+    }
+
+    pub fn update_end_of_startup(&self, ev: EndOfStartupEvent) -> Result<(), KafkaError> {
+        self.publisher.update_end_of_startup(ev)
+    }
+
+    pub fn update_account_created(&self, ev: AccountCreatedEvent) -> Result<(), KafkaError> {
+        self.publisher.update_account_created(ev)
+    }
 }
@@ -12,39 +12,58 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 use rdkafka::message::{Header, OwnedHeaders};
 use solana_program::pubkey::Pubkey;
 
 use {
     crate::{
-        message_wrapper::EventMessage::{self, Account, Slot, Transaction},
+        env_config::EnvConfigKafka,
+        message_wrapper::EventMessage::{
+            self, Account, AccountCreated, BlockMetadata, EndOfStartup, Entry, Slot, Transaction,
+        },
         prom::{
-            StatsThreadedProducerContext, UPLOAD_ACCOUNTS_TOTAL, UPLOAD_SLOTS_TOTAL,
-            UPLOAD_TRANSACTIONS_TOTAL,
+            StatsThreadedProducerContext, STARTUP_ACCOUNT_BYTES, STARTUP_QUEUE_FULL_RETRIES_TOTAL,
+            STARTUP_THROTTLED_TOTAL, UPLOAD_ACCOUNTS_CREATED_TOTAL, UPLOAD_ACCOUNTS_TOTAL,
+            UPLOAD_BLOCK_METADATA_TOTAL, UPLOAD_END_OF_STARTUP_TOTAL, UPLOAD_ENTRIES_TOTAL,
+            UPLOAD_SLOTS_TOTAL, UPLOAD_TRANSACTIONS_TOTAL,
         },
-        Cluster, Config, MessageWrapper, SlotStatusEvent, TransactionEvent, UpdateAccountEvent,
+        rate_limiter::RateLimiter,
+        topic_template::{expand, TopicTemplate},
+        AccountCreatedEvent, BlockMetadataEvent, Cluster, Config, EndOfStartupEvent, EntryEvent,
+        MessageWrapper, SlotStatusEvent, TransactionCategory, TransactionEvent, UpdateAccountEvent,
     },
     log::error,
     prost::Message,
     rdkafka::{
-        error::KafkaError,
+        error::{KafkaError, RDKafkaErrorCode},
         producer::{BaseRecord, Producer, ThreadedProducer},
     },
-    std::time::Duration,
+    std::{thread, time::Duration},
 };
 
+/// Time to wait before retrying to send a startup account while the producer queue is full.
+const QUEUE_FULL_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+
 pub struct KafkaPublisher {
     pub(crate) env: String,
     producer: ThreadedProducer<StatsThreadedProducerContext>,
     cluster: Cluster,
     shutdown_timeout: Duration,
 
-    update_account_topic: String,
-    update_account_topic_overrides: HashMap<Vec<u8>, String>,
+    update_account_topic: TopicTemplate,
+    update_account_topic_overrides: HashMap<Vec<u8>, Arc<str>>,
+    startup_account_topic: TopicTemplate,
+    startup_rate_limiter: RateLimiter,
     slot_status_topic: String,
     transaction_topic: String,
+    vote_transaction_topic: String,
+    failed_transaction_topic: String,
+    block_metadata_topic: String,
+    entry_topic: String,
+    end_of_startup_topic: String,
+    account_created_topic: TopicTemplate,
 
     wrap_messages: bool,
 }
@@ -53,33 +72,76 @@ impl KafkaPublisher {
     pub fn new(
         producer: ThreadedProducer<StatsThreadedProducerContext>,
         config: &Config,
-        env: String,
+        env_config: &EnvConfigKafka,
     ) -> Self {
+        let topics = env_config.topics(config);
+        let cluster = &config.cluster;
+        let owner_labels = Arc::new(config.owner_labels_by_account());
+        let account_topic = |template: &str, event: &str| {
+            TopicTemplate::new(template, cluster, event, owner_labels.clone())
+        };
+        // The owner of overridden accounts is known upfront
+        let update_account_topic_overrides = topics
+            .update_account_topic_overrides
+            .into_iter()
+            .map(|(owner, topic)| {
+                let topic = account_topic(&topic, "account_update").for_owner(Some(&owner));
+                (owner, topic)
+            })
+            .collect();
         Self {
-            env,
-            cluster: config.cluster.clone(),
+            env: env_config.name.clone(),
+            cluster: cluster.clone(),
             producer,
             shutdown_timeout: Duration::from_millis(config.shutdown_timeout_ms),
-            update_account_topic: config.update_account_topic.clone(),
-            update_account_topic_overrides: config.update_topic_overrides_by_account(),
-            slot_status_topic: config.slot_status_topic.clone(),
-            transaction_topic: config.transaction_topic.clone(),
-            wrap_messages: config.wrap_messages,
+            update_account_topic: account_topic(&topics.update_account_topic, "account_update"),
+            update_account_topic_overrides,
+            startup_account_topic: account_topic(&config.startup_account_topic, "startup_account"),
+            startup_rate_limiter: RateLimiter::new(
+                config.startup_max_messages_per_second,
+                config.startup_max_bytes_per_second,
+            ),
+            slot_status_topic: expand(&topics.slot_status_topic, cluster, "slot_status"),
+            transaction_topic: expand(&topics.transaction_topic, cluster, "transaction"),
+            vote_transaction_topic: expand(
+                &env_config.vote_transaction_topic,
+                cluster,
+                "transaction",
+            ),
+            failed_transaction_topic: expand(
+                &env_config.failed_transaction_topic,
+                cluster,
+                "transaction",
+            ),
+            block_metadata_topic: expand(&config.block_metadata_topic, cluster, "block_metadata"),
+            entry_topic: expand(&config.entry_topic, cluster, "entry"),
+            end_of_startup_topic: expand(&config.end_of_startup_topic, cluster, "end_of_startup"),
+            account_created_topic: account_topic(&config.account_created_topic, "account_created"),
+            wrap_messages: topics.wrap_messages,
         }
     }
 
     pub fn update_account(&self, ev: UpdateAccountEvent) -> Result<(), KafkaError> {
-        let topic = self
-            .update_account_topic_overrides
-            .get(&ev.owner)
-            .unwrap_or(&self.update_account_topic);
+        let is_startup = ev.is_startup;
+        let topic = if is_startup && !self.startup_account_topic.is_empty() {
+            self.startup_account_topic.for_owner(Some(&ev.owner))
+        } else {
+            match self.update_account_topic_overrides.get(&ev.owner) {
+                Some(topic) => topic.clone(),
+                None => self.update_account_topic.for_owner(Some(&ev.owner)),
+            }
+        };
 
         let (key, buf) = Self::account_update_key_and_data(ev, &self.cluster, self.wrap_messages);
-        let record = BaseRecord::<Vec<u8>, _>::to(topic)
+        let record = BaseRecord::<Vec<u8>, _>::to(&topic)
             .key(&key)
             .headers(Self::headers(&self.cluster))
             .payload(&buf);
-        let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
+        let result = if is_startup {
+            self.send_startup_account(record, buf.len())
+        } else {
+            self.producer.send(record).map(|_| ()).map_err(|(e, _)| e)
+        };
         UPLOAD_ACCOUNTS_TOTAL
             .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
             .inc();
@@ -106,6 +168,8 @@ impl KafkaPublisher {
     }
 
     pub fn update_transaction(&self, ev: TransactionEvent) -> Result<(), KafkaError> {
+        let topic = self.transaction_topic_of(TransactionCategory::of(&ev));
+
         let temp_key;
         let (key, buf) = if self.wrap_messages {
             temp_key = Self::copy_and_prepend(ev.signature.as_slice(), 84u8);
@@ -116,11 +180,97 @@ impl KafkaPublisher {
         } else {
             (&ev.signature, ev.encode_to_vec())
         };
-        let record = BaseRecord::<Vec<u8>, _>::to(&self.transaction_topic)
+        let record = BaseRecord::<Vec<u8>, _>::to(topic).key(key).payload(&buf);
+        let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
+        UPLOAD_TRANSACTIONS_TOTAL
+            .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
+            .inc();
+        result
+    }
+
+    pub fn update_block_metadata(&self, ev: BlockMetadataEvent) -> Result<(), KafkaError> {
+        let temp_key;
+        let (key, buf) = if self.wrap_messages {
+            temp_key = Self::copy_and_prepend(&ev.slot.to_le_bytes(), 66u8);
+            (
+                &temp_key,
+                Self::encode_with_wrapper(BlockMetadata(Box::new(ev))),
+            )
+        } else {
+            temp_key = ev.slot.to_le_bytes().to_vec();
+            (&temp_key, ev.encode_to_vec())
+        };
+        let record = BaseRecord::<Vec<u8>, _>::to(&self.block_metadata_topic)
             .key(key)
             .payload(&buf);
         let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
-        UPLOAD_TRANSACTIONS_TOTAL
+        UPLOAD_BLOCK_METADATA_TOTAL
+            .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
+            .inc();
+        result
+    }
+
+    pub fn update_entry(&self, ev: EntryEvent) -> Result<(), KafkaError> {
+        let temp_key;
+        let (key, buf) = if self.wrap_messages {
+            temp_key = Self::copy_and_prepend(&ev.slot.to_le_bytes(), 69u8);
+            (&temp_key, Self::encode_with_wrapper(Entry(Box::new(ev))))
+        } else {
+            temp_key = ev.slot.to_le_bytes().to_vec();
+            (&temp_key, ev.encode_to_vec())
+        };
+        let record = BaseRecord::<Vec<u8>, _>::to(&self.entry_topic)
+            .key(key)
+            .payload(&buf);
+        let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
+        UPLOAD_ENTRIES_TOTAL
+            .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
+            .inc();
+        result
+    }
+
+    pub fn update_end_of_startup(&self, ev: EndOfStartupEvent) -> Result<(), KafkaError> {
+        let temp_key;
+        let (key, buf) = if self.wrap_messages {
+            temp_key = Self::copy_and_prepend(&ev.slot.to_le_bytes(), 77u8);
+            (
+                &temp_key,
+                Self::encode_with_wrapper(EndOfStartup(Box::new(ev))),
+            )
+        } else {
+            temp_key = ev.slot.to_le_bytes().to_vec();
+            (&temp_key, ev.encode_to_vec())
+        };
+        let record = BaseRecord::<Vec<u8>, _>::to(&self.end_of_startup_topic)
+            .key(key)
+            .headers(Self::headers(&self.cluster))
+            .payload(&buf);
+        let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
+        UPLOAD_END_OF_STARTUP_TOTAL
+            .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
+            .inc();
+        result
+    }
+
+    pub fn update_account_created(&self, ev: AccountCreatedEvent) -> Result<(), KafkaError> {
+        let topic = self.account_created_topic.for_owner(ev.owner.as_deref());
+        let temp_key;
+        let (key, buf) = if self.wrap_messages {
+            temp_key = Self::copy_and_prepend(&ev.pubkey, 67u8);
+            (
+                &temp_key,
+                Self::encode_with_wrapper(AccountCreated(Box::new(ev))),
+            )
+        } else {
+            temp_key = ev.pubkey.clone();
+            (&temp_key, ev.encode_to_vec())
+        };
+        let record = BaseRecord::<Vec<u8>, _>::to(&topic)
+            .key(key)
+            .headers(Self::headers(&self.cluster))
+            .payload(&buf);
+        let result = self.producer.send(record).map(|_| ()).map_err(|(e, _)| e);
+        UPLOAD_ACCOUNTS_CREATED_TOTAL
             .with_label_values(&[if result.is_ok() { "success" } else { "failed" }])
             .inc();
         result
@@ -136,6 +286,75 @@ impl KafkaPublisher {
 
     pub fn wants_transaction(&self) -> bool {
         !self.transaction_topic.is_empty()
+            || !self.vote_transaction_topic.is_empty()
+            || !self.failed_transaction_topic.is_empty()
+    }
+
+    pub fn wants_transaction_of(&self, category: TransactionCategory) -> bool {
+        !self.transaction_topic_of(category).is_empty()
+    }
+
+    /// Vote and failed transactions are sent to the [transaction_topic] unless they have a
+    /// dedicated topic.
+    fn transaction_topic_of(&self, category: TransactionCategory) -> &str {
+        match category {
+            TransactionCategory::Vote if !self.vote_transaction_topic.is_empty() => {
+                &self.vote_transaction_topic
+            }
+            TransactionCategory::Failed if !self.failed_transaction_topic.is_empty() => {
+                &self.failed_transaction_topic
+            }
+            _ => &self.transaction_topic,
+        }
+    }
+
+    pub fn wants_block_metadata(&self) -> bool {
+        !self.block_metadata_topic.is_empty()
+    }
+
+    pub fn wants_entry(&self) -> bool {
+        !self.entry_topic.is_empty()
+    }
+
+    pub fn wants_end_of_startup(&self) -> bool {
+        !self.end_of_startup_topic.is_empty()
+    }
+
+    pub fn wants_account_created(&self) -> bool {
+        !self.account_created_topic.is_empty()
+    }
+
+    /// Sends a startup account, throttled to the configured rate.
+    /// Since the validator is not processing slots yet while streaming accounts on startup,
+    /// sending is retried while the producer queue is full instead of dropping the account.
+    fn send_startup_account(
+        &self,
+        mut record: BaseRecord<Vec<u8>, Vec<u8>>,
+        bytes: usize,
+    ) -> Result<(), KafkaError> {
+        if !self.startup_rate_limiter.acquire(bytes).is_zero() {
+            STARTUP_THROTTLED_TOTAL
+                .with_label_values(&[&self.env])
+                .inc();
+        }
+        loop {
+            match self.producer.send(record) {
+                Ok(()) => {
+                    STARTUP_ACCOUNT_BYTES
+                        .with_label_values(&[&self.env])
+                        .inc_by(bytes as u64);
+                    return Ok(());
+                }
+                Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), returned)) => {
+                    STARTUP_QUEUE_FULL_RETRIES_TOTAL
+                        .with_label_values(&[&self.env])
+                        .inc();
+                    thread::sleep(QUEUE_FULL_RETRY_INTERVAL);
+                    record = returned;
+                }
+                Err((e, _)) => return Err(e),
+            }
+        }
     }
 
     fn encode_with_wrapper(message: EventMessage) -> Vec<u8> {
@@ -207,6 +426,7 @@ mod tests {
     use rdkafka::message::Headers;
 
     use super::*;
+    use crate::AccountUpdateSource;
 
     const PK: &str = "A15Y2eoMNGeX4516TYTaaMErwabCrf9AB9mrzFohdQJz";
     fn event_with_owner(owner: &Pubkey) -> UpdateAccountEvent {
@@ -220,6 +440,11 @@ mod tests {
             data: "account data".as_bytes().to_vec(),
             write_version: 1,
             txn_signature: None,
+            is_startup: false,
+            source: AccountUpdateSource::Validator.into(),
+            is_deleted: false,
+            transaction_index: None,
+            account_index: None,
         }
     }
 
@@ -1,15 +1,22 @@
 use crate::{
-    allowlist::Allowlist, Config, Filter, PluginResult, SlotStatusEvent, TransactionEvent,
-    UpdateAccountEvent, SYSTEM_PROGRAMS,
+    allowlist::Allowlist, env_config::EnvConfigLocal, AccountCreatedEvent, BlockMetadataEvent,
+    Commitment, Config, EndOfStartupEvent, EntryEvent, EventPolicy, Filter, PluginResult,
+    SlotStatusEvent, TransactionCategory, TransactionEvent, TransactionMatch, UpdateAccountEvent,
+    SYSTEM_PROGRAMS,
 };
 use solana_program::pubkey::Pubkey;
-use std::{collections::HashSet, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
 
 use log::debug;
 use serde::Serialize;
 
 use super::serializable_events::{
-    SerializableSlotStatusEvent, SerializableTransactionEvent, SerializableUpdateAccountEvent,
+    SerializableAccountCreatedEvent, SerializableBlockMetadataEvent, SerializableEndOfStartupEvent,
+    SerializableEntryEvent, SerializableMessageWrapper, SerializableSlotStatusEvent,
+    SerializableTransactionEvent, SerializableUpdateAccountEvent,
 };
 
 // -----------------
@@ -19,33 +26,46 @@ pub struct LocalPublisher {
     pub(crate) env: String,
     filter: Filter,
     update_account_path: String,
+    update_account_path_overrides: HashMap<Vec<u8>, String>,
+    startup_account_path: String,
     update_slot_status_path: String,
     update_transaction_path: String,
+    vote_transaction_path: String,
+    failed_transaction_path: String,
+    update_block_metadata_path: String,
+    update_entry_path: String,
+    update_end_of_startup_path: String,
+    account_created_path: String,
     root_url: String,
+    wrap_messages: bool,
     include_system_accounts: bool,
     system_programs: HashSet<[u8; 32]>,
 }
 
 impl LocalPublisher {
-    pub fn new(
-        filter: Filter,
-        config: &Config,
-        env: String,
-        root_url: String,
-        include_system_accounts: bool,
-    ) -> Self {
+    pub fn new(filter: Filter, config: &Config, env_config: &EnvConfigLocal) -> Self {
         let system_programs = SYSTEM_PROGRAMS
             .iter()
             .map(|s| Pubkey::from_str(s).unwrap().to_bytes())
             .collect::<HashSet<_>>();
+        let topics = env_config.topics(config);
         Self {
-            env,
-            update_account_path: config.update_account_topic.clone(),
-            update_slot_status_path: config.slot_status_topic.clone(),
-            update_transaction_path: config.transaction_topic.clone(),
-            root_url,
+            env: env_config.name.clone(),
+            update_account_path: topics.update_account_topic,
+            update_account_path_overrides: topics.update_account_topic_overrides,
+            startup_account_path: config.startup_account_topic.clone(),
+            update_slot_status_path: topics.slot_status_topic,
+            update_transaction_path: topics.transaction_topic,
+            vote_transaction_path: env_config.vote_transaction_topic.clone(),
+            failed_transaction_path: env_config.failed_transaction_topic.clone(),
+            update_block_metadata_path: config.block_metadata_topic.clone(),
+            update_entry_path: config.entry_topic.clone(),
+            update_end_of_startup_path: config.end_of_startup_topic.clone(),
+            account_created_path: config.account_created_topic.clone(),
+            root_url: env_config.url.clone(),
+            wrap_messages: topics.wrap_messages,
             filter,
-            include_system_accounts,
+            include_system_accounts: env_config.include_system_accounts,
             system_programs,
         }
     }
@@ -57,12 +77,34 @@ impl LocalPublisher {
         self.filter.get_allowlist()
     }
 
+    pub fn get_denylist(&self) -> Allowlist {
+        self.filter.get_denylist()
+    }
+
     pub fn wants_account_key(&self, account_key: &[u8]) -> bool {
         if self.filter.allow_list_is_empty() && !self.include_system_accounts {
             let slice: &[u8; 32] = account_key[0..32].try_into().unwrap();
-            return !self.system_programs.contains(slice);
+            if self.system_programs.contains(slice) {
+                return false;
+            }
         }
-        self.filter.wants_account_key(account_key, true)
+        self.filter.wants_account_key(account_key)
+    }
+
+    pub fn policy(&self) -> EventPolicy {
+        self.filter.policy()
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        self.filter.commitment()
+    }
+
+    pub fn transaction_match(&self) -> TransactionMatch {
+        self.filter.transaction_match()
+    }
+
+    pub fn wants_transaction_category(&self, category: TransactionCategory) -> bool {
+        self.filter.wants_transaction_category(category)
     }
 
     // -----------------
@@ -82,31 +124,121 @@ impl LocalPublisher {
 
     pub fn wants_transaction(&self) -> bool {
         !self.update_transaction_path.is_empty()
+            || !self.vote_transaction_path.is_empty()
+            || !self.failed_transaction_path.is_empty()
+    }
+
+    pub fn wants_transaction_of(&self, category: TransactionCategory) -> bool {
+        !self.transaction_path_of(category).is_empty()
+    }
+
+    /// Vote and failed transactions are posted to the [update_transaction_path] unless they
+    /// have a dedicated path.
+    fn transaction_path_of(&self, category: TransactionCategory) -> &str {
+        match category {
+            TransactionCategory::Vote if !self.vote_transaction_path.is_empty() => {
+                &self.vote_transaction_path
+            }
+            TransactionCategory::Failed if !self.failed_transaction_path.is_empty() => {
+                &self.failed_transaction_path
+            }
+            _ => &self.update_transaction_path,
+        }
+    }
+
+    pub fn wants_block_metadata(&self) -> bool {
+        !self.update_block_metadata_path.is_empty()
+    }
+
+    pub fn wants_entry(&self) -> bool {
+        !self.update_entry_path.is_empty()
+    }
+
+    pub fn wants_end_of_startup(&self) -> bool {
+        !self.update_end_of_startup_path.is_empty()
+    }
+
+    pub fn wants_account_created(&self) -> bool {
+        !self.account_created_path.is_empty()
     }
 
     pub fn update_account(&self, ev: UpdateAccountEvent) -> PluginResult<()> {
+        let path = if ev.is_startup && !self.startup_account_path.is_empty() {
+            &self.startup_account_path
+        } else {
+            self.update_account_path_overrides
+                .get(&ev.owner)
+                .unwrap_or(&self.update_account_path)
+        };
         self.publish_event(
-            &self.update_account_path,
-            &SerializableUpdateAccountEvent::from(ev),
+            path,
+            SerializableUpdateAccountEvent::from(ev),
+            SerializableMessageWrapper::Account,
         )
     }
 
     pub fn update_slot_status(&self, ev: SlotStatusEvent) -> PluginResult<()> {
         self.publish_event(
             &self.update_slot_status_path,
-            &SerializableSlotStatusEvent::from(ev),
+            SerializableSlotStatusEvent::from(ev),
+            SerializableMessageWrapper::Slot,
         )
     }
 
     pub fn update_transaction(&self, ev: TransactionEvent) -> PluginResult<()> {
+        let path = self.transaction_path_of(TransactionCategory::of(&ev));
+        self.publish_event(
+            path,
+            SerializableTransactionEvent::from(ev),
+            SerializableMessageWrapper::Transaction,
+        )
+    }
+
+    pub fn update_block_metadata(&self, ev: BlockMetadataEvent) -> PluginResult<()> {
+        self.publish_event(
+            &self.update_block_metadata_path,
+            SerializableBlockMetadataEvent::from(ev),
+            SerializableMessageWrapper::BlockMetadata,
+        )
+    }
+
+    pub fn update_entry(&self, ev: EntryEvent) -> PluginResult<()> {
+        self.publish_event(
+            &self.update_entry_path,
+            SerializableEntryEvent::from(ev),
+            SerializableMessageWrapper::Entry,
+        )
+    }
+
+    pub fn update_end_of_startup(&self, ev: EndOfStartupEvent) -> PluginResult<()> {
         self.publish_event(
-            &self.update_transaction_path,
-            &SerializableTransactionEvent::from(ev),
+            &self.update_end_of_startup_path,
+            SerializableEndOfStartupEvent::from(ev),
+            SerializableMessageWrapper::EndOfStartup,
         )
     }
 
-    fn publish_event<T: Serialize>(&self, path: &str, ev: &T) -> PluginResult<()> {
-        let payload = serde_json::to_vec(ev).map_err(Box::new)?;
+    pub fn update_account_created(&self, ev: AccountCreatedEvent) -> PluginResult<()> {
+        self.publish_event(
+            &self.account_created_path,
+            SerializableAccountCreatedEvent::from(ev),
+            SerializableMessageWrapper::AccountCreated,
+        )
+    }
+
+    /// Posts [ev] as JSON, wrapped by [wrap] if [wrap_messages] is set.
+    fn publish_event<T: Serialize>(
+        &self,
+        path: &str,
+        ev: T,
+        wrap: impl FnOnce(T) -> SerializableMessageWrapper,
+    ) -> PluginResult<()> {
+        let payload = if self.wrap_messages {
+            serde_json::to_vec(&wrap(ev))
+        } else {
+            serde_json::to_vec(&ev)
+        }
+        .map_err(Box::new)?;
         let uri = format!("{}/{}", self.root_url, path);
         ureq::post(&uri)
             .set("Content-Type", "application/json")
@@ -1,18 +1,24 @@
 mod filtering_publisher;
 pub mod kafka_publisher;
 mod local_publisher;
+mod queued_publisher;
 pub mod serializable_events;
 
 use crate::{
-    allowlist::Allowlist, PluginResult, SlotStatusEvent, TransactionEvent, UpdateAccountEvent,
+    allowlist::Allowlist, AccountCreatedEvent, BlockMetadataEvent, Commitment, EndOfStartupEvent,
+    EntryEvent, EventPolicy, PluginResult, SlotStatusEvent, TransactionCategory, TransactionEvent,
+    TransactionMatch, UpdateAccountEvent,
 };
 pub use filtering_publisher::FilteringPublisher;
 pub use local_publisher::LocalPublisher;
+pub use queued_publisher::{OverflowPolicy, QueueConfig, QueuedEvent, QueuedPublisher};
 
+#[allow(clippy::enum_variant_names)]
 pub enum Publisher {
     FilteringPublisher(FilteringPublisher),
     #[allow(unused)]
     LocalPublisher(LocalPublisher),
+    QueuedPublisher(QueuedPublisher),
 }
 
 impl Publisher {
@@ -23,6 +29,15 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.get_allowlist(),
             Publisher::LocalPublisher(p) => p.get_allowlist(),
+            Publisher::QueuedPublisher(p) => p.publisher().get_allowlist(),
+        }
+    }
+
+    pub fn get_denylist(&self) -> Allowlist {
+        match self {
+            Publisher::FilteringPublisher(p) => p.get_denylist(),
+            Publisher::LocalPublisher(p) => p.get_denylist(),
+            Publisher::QueuedPublisher(p) => p.publisher().get_denylist(),
         }
     }
 
@@ -30,6 +45,39 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.wants_account_key(account_key),
             Publisher::LocalPublisher(p) => p.wants_account_key(account_key),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_account_key(account_key),
+        }
+    }
+
+    pub fn policy(&self) -> EventPolicy {
+        match self {
+            Publisher::FilteringPublisher(p) => p.policy(),
+            Publisher::LocalPublisher(p) => p.policy(),
+            Publisher::QueuedPublisher(p) => p.publisher().policy(),
+        }
+    }
+
+    pub fn commitment(&self) -> Commitment {
+        match self {
+            Publisher::FilteringPublisher(p) => p.commitment(),
+            Publisher::LocalPublisher(p) => p.commitment(),
+            Publisher::QueuedPublisher(p) => p.publisher().commitment(),
+        }
+    }
+
+    pub fn transaction_match(&self) -> TransactionMatch {
+        match self {
+            Publisher::FilteringPublisher(p) => p.transaction_match(),
+            Publisher::LocalPublisher(p) => p.transaction_match(),
+            Publisher::QueuedPublisher(p) => p.publisher().transaction_match(),
+        }
+    }
+
+    pub fn wants_transaction_category(&self, category: TransactionCategory) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_transaction_category(category),
+            Publisher::LocalPublisher(p) => p.wants_transaction_category(category),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_transaction_category(category),
         }
     }
 
@@ -40,6 +88,7 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.env(),
             Publisher::LocalPublisher(p) => p.env(),
+            Publisher::QueuedPublisher(p) => p.publisher().env(),
         }
     }
 
@@ -47,6 +96,7 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.wants_update_account(),
             Publisher::LocalPublisher(p) => p.wants_update_account(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_update_account(),
         }
     }
 
@@ -54,6 +104,7 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.wants_slot_status(),
             Publisher::LocalPublisher(p) => p.wants_slot_status(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_slot_status(),
         }
     }
 
@@ -61,6 +112,48 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.wants_transaction(),
             Publisher::LocalPublisher(p) => p.wants_transaction(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_transaction(),
+        }
+    }
+
+    /// Whether transactions of [category] have a topic, see [Publisher::wants_transaction].
+    pub fn wants_transaction_of(&self, category: TransactionCategory) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_transaction_of(category),
+            Publisher::LocalPublisher(p) => p.wants_transaction_of(category),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_transaction_of(category),
+        }
+    }
+
+    pub fn wants_block_metadata(&self) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_block_metadata(),
+            Publisher::LocalPublisher(p) => p.wants_block_metadata(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_block_metadata(),
+        }
+    }
+
+    pub fn wants_entry(&self) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_entry(),
+            Publisher::LocalPublisher(p) => p.wants_entry(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_entry(),
+        }
+    }
+
+    pub fn wants_end_of_startup(&self) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_end_of_startup(),
+            Publisher::LocalPublisher(p) => p.wants_end_of_startup(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_end_of_startup(),
+        }
+    }
+
+    pub fn wants_account_created(&self) -> bool {
+        match self {
+            Publisher::FilteringPublisher(p) => p.wants_account_created(),
+            Publisher::LocalPublisher(p) => p.wants_account_created(),
+            Publisher::QueuedPublisher(p) => p.publisher().wants_account_created(),
         }
     }
 
@@ -68,6 +161,7 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.update_account(ev).map_err(Box::new)?,
             Publisher::LocalPublisher(p) => p.update_account(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::Account(ev)),
         }
         Ok(())
     }
@@ -76,6 +170,7 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.update_slot_status(ev).map_err(Box::new)?,
             Publisher::LocalPublisher(p) => p.update_slot_status(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::SlotStatus(ev)),
         }
         Ok(())
     }
@@ -84,6 +179,43 @@ impl Publisher {
         match self {
             Publisher::FilteringPublisher(p) => p.update_transaction(ev).map_err(Box::new)?,
             Publisher::LocalPublisher(p) => p.update_transaction(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::Transaction(Box::new(ev))),
+        }
+        Ok(())
+    }
+
+    pub fn update_block_metadata(&self, ev: BlockMetadataEvent) -> PluginResult<()> {
+        match self {
+            Publisher::FilteringPublisher(p) => p.update_block_metadata(ev).map_err(Box::new)?,
+            Publisher::LocalPublisher(p) => p.update_block_metadata(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::BlockMetadata(ev)),
+        }
+        Ok(())
+    }
+
+    pub fn update_entry(&self, ev: EntryEvent) -> PluginResult<()> {
+        match self {
+            Publisher::FilteringPublisher(p) => p.update_entry(ev).map_err(Box::new)?,
+            Publisher::LocalPublisher(p) => p.update_entry(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::Entry(ev)),
+        }
+        Ok(())
+    }
+
+    pub fn update_end_of_startup(&self, ev: EndOfStartupEvent) -> PluginResult<()> {
+        match self {
+            Publisher::FilteringPublisher(p) => p.update_end_of_startup(ev).map_err(Box::new)?,
+            Publisher::LocalPublisher(p) => p.update_end_of_startup(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::EndOfStartup(ev)),
+        }
+        Ok(())
+    }
+
+    pub fn update_account_created(&self, ev: AccountCreatedEvent) -> PluginResult<()> {
+        match self {
+            Publisher::FilteringPublisher(p) => p.update_account_created(ev).map_err(Box::new)?,
+            Publisher::LocalPublisher(p) => p.update_account_created(ev)?,
+            Publisher::QueuedPublisher(p) => p.push(QueuedEvent::AccountCreated(ev)),
         }
         Ok(())
     }
@@ -0,0 +1,601 @@
+use std::{
+    collections::{hash_map::DefaultHasher, VecDeque},
+    hash::{Hash, Hasher},
+    sync::{Arc, Barrier, Condvar, Mutex},
+    thread::{self, JoinHandle},
+};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prom::{PUBLISH_QUEUE_DEPTH, PUBLISH_QUEUE_DROPPED_TOTAL},
+    startup::StartupProgress,
+    AccountCreatedEvent, BlockMetadataEvent, EndOfStartupEvent, EntryEvent, PluginResult,
+    SlotStatusEvent, TransactionEvent, UpdateAccountEvent,
+};
+
+use super::Publisher;
+
+/// What to do with an event when the queue of an environment is full.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Block the validator callback until there is room in the queue.
+    #[default]
+    Block,
+    /// Drop the oldest queued event to make room for the new one.
+    DropOldest,
+    /// Drop the new event.
+    DropNewest,
+}
+
+impl OverflowPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverflowPolicy::Block => "block",
+            OverflowPolicy::DropOldest => "drop_oldest",
+            OverflowPolicy::DropNewest => "drop_newest",
+        }
+    }
+}
+
+/// Config of the queue between the validator callbacks and the publisher of an environment.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QueueConfig {
+    /// Maximum number of queued events, `0` to publish synchronously.
+    pub capacity: usize,
+    /// Number of threads publishing the queued events, at least one. Each has its own queue
+    /// with an even share of the [capacity].
+    pub workers: usize,
+    pub overflow_policy: OverflowPolicy,
+}
+
+pub enum QueuedEvent {
+    Account(UpdateAccountEvent),
+    SlotStatus(SlotStatusEvent),
+    Transaction(Box<TransactionEvent>),
+    BlockMetadata(BlockMetadataEvent),
+    Entry(EntryEvent),
+    EndOfStartup(EndOfStartupEvent),
+    AccountCreated(AccountCreatedEvent),
+}
+
+impl QueuedEvent {
+    /// Publishes the event and records the startup accounts that were published.
+    fn publish(
+        self,
+        publisher: &Publisher,
+        startup_progress: &StartupProgress,
+    ) -> PluginResult<()> {
+        match self {
+            QueuedEvent::Account(ev) if ev.is_startup => {
+                let (owner, slot) = (ev.owner.clone(), ev.slot);
+                publisher.update_account(ev)?;
+                startup_progress.record(publisher.env(), &owner, slot);
+                Ok(())
+            }
+            QueuedEvent::Account(ev) => publisher.update_account(ev),
+            QueuedEvent::SlotStatus(ev) => publisher.update_slot_status(ev),
+            QueuedEvent::Transaction(ev) => publisher.update_transaction(*ev),
+            QueuedEvent::BlockMetadata(ev) => publisher.update_block_metadata(ev),
+            QueuedEvent::Entry(ev) => publisher.update_entry(ev),
+            QueuedEvent::EndOfStartup(_) => publish_end_of_startup(publisher, startup_progress),
+            QueuedEvent::AccountCreated(ev) => publisher.update_account_created(ev),
+        }
+    }
+
+    /// Neither startup accounts nor the end of startup are ever dropped.
+    fn is_startup(&self) -> bool {
+        match self {
+            QueuedEvent::Account(ev) => ev.is_startup,
+            QueuedEvent::EndOfStartup(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the worker that publishes the event. Events of the same account or slot are
+    /// published by the same worker and thus in order.
+    fn worker(&self, workers: usize) -> usize {
+        let mut hasher = DefaultHasher::new();
+        match self {
+            QueuedEvent::Account(ev) => ev.pubkey.hash(&mut hasher),
+            QueuedEvent::AccountCreated(ev) => ev.pubkey.hash(&mut hasher),
+            QueuedEvent::Transaction(ev) => ev.signature.hash(&mut hasher),
+            QueuedEvent::SlotStatus(ev) => ev.slot.hash(&mut hasher),
+            QueuedEvent::BlockMetadata(ev) => ev.slot.hash(&mut hasher),
+            QueuedEvent::Entry(ev) => ev.slot.hash(&mut hasher),
+            QueuedEvent::EndOfStartup(_) => {}
+        }
+        (hasher.finish() % workers as u64) as usize
+    }
+}
+
+/// Publishes the end of startup with the counts of the startup accounts published so far,
+/// which includes all of those queued before it.
+fn publish_end_of_startup(
+    publisher: &Publisher,
+    startup_progress: &StartupProgress,
+) -> PluginResult<()> {
+    let event = startup_progress.end_of_startup_event(publisher.env());
+    info!(
+        "Publishing end of startup to {} environment after {} accounts",
+        publisher.env(),
+        event.total_accounts
+    );
+    publisher.update_end_of_startup(event)
+}
+
+/// Item of the queue of a single worker.
+enum QueueItem {
+    Event(QueuedEvent),
+    /// Queued for all workers, the end of startup is published once every worker published
+    /// the events queued before it.
+    EndOfStartup(Arc<Barrier>),
+}
+
+impl QueueItem {
+    fn publish(
+        self,
+        publisher: &Publisher,
+        startup_progress: &StartupProgress,
+    ) -> PluginResult<()> {
+        match self {
+            QueueItem::Event(event) => event.publish(publisher, startup_progress),
+            QueueItem::EndOfStartup(barrier) => {
+                if barrier.wait().is_leader() {
+                    publish_end_of_startup(publisher, startup_progress)
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Neither startup accounts nor the end of startup that the other workers wait for are
+    /// ever dropped.
+    fn is_startup(&self) -> bool {
+        match self {
+            QueueItem::Event(event) => event.is_startup(),
+            QueueItem::EndOfStartup(_) => true,
+        }
+    }
+}
+
+// -----------------
+// QueuedPublisher
+// -----------------
+
+/// Hands events to worker threads via bounded queues so that a slow Kafka cluster or endpoint
+/// does not stall the validator callbacks. The workers record the startup accounts once they
+/// are published and build the end of startup from those.
+pub struct QueuedPublisher {
+    publisher: Arc<Publisher>,
+    queues: Vec<Arc<PublishQueue>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl QueuedPublisher {
+    pub fn new(
+        publisher: Publisher,
+        config: QueueConfig,
+        startup_progress: Arc<StartupProgress>,
+    ) -> Self {
+        let publisher = Arc::new(publisher);
+        let num_workers = config.workers.max(1);
+        let queues = (0..num_workers)
+            .map(|_| {
+                Arc::new(PublishQueue::new(
+                    publisher.env().to_string(),
+                    (config.capacity / num_workers).max(1),
+                    config.overflow_policy,
+                ))
+            })
+            .collect::<Vec<_>>();
+        let workers = queues
+            .iter()
+            .enumerate()
+            .map(|(idx, queue)| {
+                let publisher = publisher.clone();
+                let queue = queue.clone();
+                let startup_progress = startup_progress.clone();
+                thread::Builder::new()
+                    .name(format!("publish-{}-{}", publisher.env(), idx))
+                    .spawn(move || {
+                        while let Some(item) = queue.pop() {
+                            if let Err(err) = item.publish(&publisher, &startup_progress) {
+                                error!("Error: {} in {} environment", err, publisher.env());
+                            }
+                        }
+                    })
+                    .expect("failed to spawn publisher thread")
+            })
+            .collect();
+        Self {
+            publisher,
+            queues,
+            workers,
+        }
+    }
+
+    pub fn publisher(&self) -> &Publisher {
+        &self.publisher
+    }
+
+    pub fn push(&self, event: QueuedEvent) {
+        match event {
+            QueuedEvent::EndOfStartup(_) if self.queues.len() > 1 => {
+                let barrier = Arc::new(Barrier::new(self.queues.len()));
+                for queue in &self.queues {
+                    queue.push(QueueItem::EndOfStartup(barrier.clone()));
+                }
+            }
+            event => {
+                let worker = event.worker(self.queues.len());
+                self.queues[worker].push(QueueItem::Event(event))
+            }
+        }
+    }
+}
+
+impl Drop for QueuedPublisher {
+    fn drop(&mut self) {
+        // Workers publish all remaining events before they exit
+        for queue in &self.queues {
+            queue.close();
+        }
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                error!(
+                    "Publisher thread of {} environment panicked",
+                    self.publisher.env()
+                );
+            }
+        }
+    }
+}
+
+// -----------------
+// PublishQueue
+// -----------------
+
+/// Label of [PUBLISH_QUEUE_DROPPED_TOTAL] for events pushed after the queue was closed.
+const CLOSED: &str = "closed";
+
+struct PublishQueue {
+    env: String,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    events: VecDeque<QueueItem>,
+    closed: bool,
+}
+
+impl PublishQueue {
+    fn new(env: String, capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        Self {
+            env,
+            capacity,
+            overflow_policy,
+            state: Mutex::default(),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    fn push(&self, event: QueueItem) {
+        let mut state = self.state.lock().expect("publish queue mutex poisoned");
+        // No worker drains the queue anymore
+        if state.closed {
+            self.dropped(CLOSED);
+            return;
+        }
+        if state.events.len() >= self.capacity {
+            // Startup events are never dropped, see KafkaPublisher::send_startup_account.
+            // Thus the oldest event that is not a startup event is evicted.
+            let evict = match self.overflow_policy {
+                OverflowPolicy::DropOldest if !event.is_startup() => {
+                    state.events.iter().position(|queued| !queued.is_startup())
+                }
+                _ => None,
+            };
+            let overflow_policy = match self.overflow_policy {
+                _ if event.is_startup() => OverflowPolicy::Block,
+                OverflowPolicy::DropOldest if evict.is_none() => OverflowPolicy::Block,
+                overflow_policy => overflow_policy,
+            };
+            match overflow_policy {
+                OverflowPolicy::Block => {
+                    while state.events.len() >= self.capacity && !state.closed {
+                        state = self
+                            .not_full
+                            .wait(state)
+                            .expect("publish queue mutex poisoned");
+                    }
+                    if state.closed {
+                        self.dropped(CLOSED);
+                        return;
+                    }
+                }
+                OverflowPolicy::DropOldest => {
+                    state.events.remove(evict.unwrap_or_default());
+                    PUBLISH_QUEUE_DEPTH.with_label_values(&[&self.env]).dec();
+                    self.dropped(self.overflow_policy.as_str());
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped(self.overflow_policy.as_str());
+                    return;
+                }
+            }
+        }
+        state.events.push_back(event);
+        // The queues of all workers of an environment add up to its depth
+        PUBLISH_QUEUE_DEPTH.with_label_values(&[&self.env]).inc();
+        self.not_empty.notify_one();
+    }
+
+    /// Returns the next event or `None` once the queue is closed and empty.
+    fn pop(&self) -> Option<QueueItem> {
+        let mut state = self.state.lock().expect("publish queue mutex poisoned");
+        loop {
+            if let Some(event) = state.events.pop_front() {
+                PUBLISH_QUEUE_DEPTH.with_label_values(&[&self.env]).dec();
+                self.not_full.notify_one();
+                return Some(event);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .not_empty
+                .wait(state)
+                .expect("publish queue mutex poisoned");
+        }
+    }
+
+    fn close(&self) {
+        self.state
+            .lock()
+            .expect("publish queue mutex poisoned")
+            .closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    fn dropped(&self, reason: &str) {
+        PUBLISH_QUEUE_DROPPED_TOTAL
+            .with_label_values(&[&self.env, reason])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::{Config, KafkaPlugin};
+
+    fn account_event(slot: u64, is_startup: bool) -> QueueItem {
+        QueueItem::Event(QueuedEvent::Account(UpdateAccountEvent {
+            slot,
+            is_startup,
+            ..Default::default()
+        }))
+    }
+
+    fn queued_slots(queue: &PublishQueue) -> Vec<u64> {
+        queue.close();
+        let mut slots = vec![];
+        while let Some(event) = queue.pop() {
+            match event {
+                QueueItem::Event(QueuedEvent::Account(ev)) => slots.push(ev.slot),
+                _ => unreachable!(),
+            }
+        }
+        slots
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let queue = PublishQueue::new("dev".to_string(), 2, OverflowPolicy::DropOldest);
+        for slot in 1..=3 {
+            queue.push(account_event(slot, false));
+        }
+        assert_eq!(queued_slots(&queue), vec![2, 3]);
+    }
+
+    #[test]
+    fn drop_oldest_keeps_startup_accounts() {
+        let queue = Arc::new(PublishQueue::new(
+            "dev".to_string(),
+            2,
+            OverflowPolicy::DropOldest,
+        ));
+        queue.push(account_event(1, true));
+        queue.push(account_event(2, true));
+
+        // Only startup accounts are queued, so the next event blocks
+        let pusher = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push(account_event(3, false)))
+        };
+        assert!(
+            matches!(queue.pop(), Some(QueueItem::Event(QueuedEvent::Account(ev))) if ev.slot == 1)
+        );
+        pusher.join().unwrap();
+
+        queue.push(account_event(4, false));
+        assert_eq!(queued_slots(&queue), vec![2, 4]);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let queue = PublishQueue::new("dev".to_string(), 2, OverflowPolicy::DropNewest);
+        for slot in 1..=3 {
+            queue.push(account_event(slot, false));
+        }
+        assert_eq!(queued_slots(&queue), vec![1, 2]);
+    }
+
+    #[test]
+    fn keep_end_of_startup() {
+        for overflow_policy in [OverflowPolicy::DropOldest, OverflowPolicy::DropNewest] {
+            let queue = Arc::new(PublishQueue::new("dev".to_string(), 2, overflow_policy));
+            queue.push(account_event(1, false));
+            queue.push(account_event(2, false));
+
+            // A single worker queues the end of startup as a plain event, which blocks as well
+            let pusher = {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    queue.push(QueueItem::Event(QueuedEvent::EndOfStartup(
+                        EndOfStartupEvent::default(),
+                    )))
+                })
+            };
+            assert!(
+                matches!(queue.pop(), Some(QueueItem::Event(QueuedEvent::Account(ev))) if ev.slot == 1)
+            );
+            pusher.join().unwrap();
+
+            queue.close();
+            assert!(
+                matches!(queue.pop(), Some(QueueItem::Event(QueuedEvent::Account(ev))) if ev.slot == 2)
+            );
+            assert!(matches!(
+                queue.pop(),
+                Some(QueueItem::Event(QueuedEvent::EndOfStartup(_)))
+            ));
+            assert!(queue.pop().is_none());
+        }
+    }
+
+    /// Answers each HTTP request with [status] and returns the url to send them to.
+    fn serve(status: u16) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_lowercase();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(len) = line.strip_prefix("content-length:") {
+                        content_length = len.trim().parse().unwrap();
+                    }
+                }
+                reader.read_exact(&mut vec![0; content_length]).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn records_published_startup_accounts() {
+        for (status, accounts) in [(200, 2), (500, 0)] {
+            let config: Config = serde_json::from_str(&format!(
+                r#"{{ "environments": [{{ "name": "dev", "url": "{}", "queue_capacity": 4 }}] }}"#,
+                serve(status)
+            ))
+            .unwrap();
+            let startup_progress = Arc::new(StartupProgress::new(["dev"]));
+            let publisher =
+                KafkaPlugin::create_publisher(&config, &config.environments[0], &startup_progress)
+                    .unwrap();
+            for slot in 1..=2 {
+                publisher
+                    .update_account(UpdateAccountEvent {
+                        slot,
+                        is_startup: true,
+                        pubkey: vec![slot as u8; 32],
+                        owner: vec![1; 32],
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+
+            // Dropping the publisher publishes the queued accounts
+            drop(publisher);
+            assert_eq!(
+                startup_progress.end_of_startup_event("dev").total_accounts,
+                accounts
+            );
+        }
+    }
+
+    #[test]
+    fn worker_per_account() {
+        let event = |pubkey: u8, slot: u64| {
+            QueuedEvent::Account(UpdateAccountEvent {
+                pubkey: vec![pubkey; 32],
+                slot,
+                ..Default::default()
+            })
+        };
+        for pubkey in 0..16 {
+            assert_eq!(event(pubkey, 1).worker(4), event(pubkey, 2).worker(4));
+        }
+        let workers = (0..64)
+            .map(|pubkey| event(pubkey, 1).worker(4))
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(workers.len(), 4);
+    }
+
+    #[test]
+    fn drop_after_close() {
+        for overflow_policy in [
+            OverflowPolicy::Block,
+            OverflowPolicy::DropOldest,
+            OverflowPolicy::DropNewest,
+        ] {
+            let queue = PublishQueue::new("dev".to_string(), 2, overflow_policy);
+            queue.push(account_event(1, false));
+            queue.close();
+            queue.push(account_event(2, false));
+            queue.push(account_event(3, true));
+            assert_eq!(queued_slots(&queue), vec![1]);
+        }
+    }
+
+    #[test]
+    fn block_until_popped() {
+        let queue = Arc::new(PublishQueue::new(
+            "dev".to_string(),
+            1,
+            OverflowPolicy::DropNewest,
+        ));
+        queue.push(account_event(1, false));
+
+        // Startup accounts block instead of being dropped
+        let pusher = {
+            let queue = queue.clone();
+            thread::spawn(move || queue.push(account_event(2, true)))
+        };
+        assert!(
+            matches!(queue.pop(), Some(QueueItem::Event(QueuedEvent::Account(ev))) if ev.slot == 1)
+        );
+        pusher.join().unwrap();
+        assert_eq!(queued_slots(&queue), vec![2]);
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
-    sanitized_message::MessagePayload, CompiledInstruction, InnerInstruction, InnerInstructions,
+    sanitized_message::MessagePayload, AccountCreatedEvent, BlockMetadataEvent,
+    CompiledInstruction, EndOfStartupEvent, EntryEvent, InnerInstruction, InnerInstructions,
     LegacyLoadedMessage, LegacyMessage, LoadedAddresses, MessageAddressTableLookup, MessageHeader,
-    Reward, SanitizedMessage, SanitizedTransaction, SlotStatusEvent, TransactionEvent,
-    TransactionStatusMeta, TransactionTokenBalance, UiTokenAmount, UpdateAccountEvent,
-    V0LoadedMessage, V0Message,
+    ProgramAccountCount, Reward, SanitizedMessage, SanitizedTransaction, SlotStatusEvent,
+    TransactionEvent, TransactionStatusMeta, TransactionTokenBalance, UiTokenAmount,
+    UpdateAccountEvent, V0LoadedMessage, V0Message,
 };
 use serde::Serialize;
 
@@ -21,6 +22,27 @@ pub struct SerializableUpdateAccountEvent {
     data: Vec<u8>,
     write_version: u64,
     txn_signature: Option<Vec<u8>>,
+    is_startup: bool,
+    source: SerializableAccountUpdateSource,
+    is_deleted: bool,
+    transaction_index: Option<u64>,
+    account_index: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub enum SerializableAccountUpdateSource {
+    Validator,
+    TransactionDeletion,
+}
+
+impl From<i32> for SerializableAccountUpdateSource {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => Self::Validator,
+            1 => Self::TransactionDeletion,
+            _ => panic!("Invalid account update source"),
+        }
+    }
 }
 
 impl From<UpdateAccountEvent> for SerializableUpdateAccountEvent {
@@ -35,6 +57,11 @@ impl From<UpdateAccountEvent> for SerializableUpdateAccountEvent {
             data: ev.data,
             write_version: ev.write_version,
             txn_signature: ev.txn_signature,
+            is_startup: ev.is_startup,
+            source: SerializableAccountUpdateSource::from(ev.source),
+            is_deleted: ev.is_deleted,
+            transaction_index: ev.transaction_index,
+            account_index: ev.account_index,
         }
     }
 }
@@ -47,6 +74,7 @@ pub enum SerializableSlotStatus {
     Processed,
     Rooted,
     Confirmed,
+    Dead,
 }
 
 impl From<i32> for SerializableSlotStatus {
@@ -55,6 +83,7 @@ impl From<i32> for SerializableSlotStatus {
             0 => Self::Processed,
             1 => Self::Rooted,
             2 => Self::Confirmed,
+            3 => Self::Dead,
             _ => panic!("Invalid slot status"),
         }
     }
@@ -424,7 +453,7 @@ pub struct SerializableTransactionEvent {
     pub transaction: Option<SerializableSanitizedTransaction>,
     pub transaction_status_meta: Option<SerializableTransactionStatusMeta>,
     pub slot: u64,
-    pub index: u64,
+    pub index: Option<u64>,
 }
 
 impl From<TransactionEvent> for SerializableTransactionEvent {
@@ -441,3 +470,149 @@ impl From<TransactionEvent> for SerializableTransactionEvent {
         }
     }
 }
+
+// -----------------
+// BlockMetadataEvent
+// -----------------
+#[derive(Debug, Serialize)]
+pub struct SerializableBlockMetadataEvent {
+    pub slot: u64,
+    pub blockhash: String,
+    pub rewards: Vec<SerializableReward>,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    pub parent_slot: u64,
+    pub parent_blockhash: String,
+    pub executed_transaction_count: u64,
+    pub entry_count: u64,
+}
+
+impl From<BlockMetadataEvent> for SerializableBlockMetadataEvent {
+    fn from(x: BlockMetadataEvent) -> Self {
+        Self {
+            slot: x.slot,
+            blockhash: x.blockhash,
+            rewards: x
+                .rewards
+                .into_iter()
+                .map(SerializableReward::from)
+                .collect(),
+            block_time: x.block_time,
+            block_height: x.block_height,
+            parent_slot: x.parent_slot,
+            parent_blockhash: x.parent_blockhash,
+            executed_transaction_count: x.executed_transaction_count,
+            entry_count: x.entry_count,
+        }
+    }
+}
+
+// -----------------
+// EntryEvent
+// -----------------
+#[derive(Debug, Serialize)]
+pub struct SerializableEntryEvent {
+    pub slot: u64,
+    pub index: u64,
+    pub num_hashes: u64,
+    pub hash: Vec<u8>,
+    pub executed_transaction_count: u64,
+}
+
+impl From<EntryEvent> for SerializableEntryEvent {
+    fn from(x: EntryEvent) -> Self {
+        Self {
+            slot: x.slot,
+            index: x.index,
+            num_hashes: x.num_hashes,
+            hash: x.hash,
+            executed_transaction_count: x.executed_transaction_count,
+        }
+    }
+}
+
+// -----------------
+// EndOfStartupEvent
+// -----------------
+#[derive(Debug, Serialize)]
+pub struct SerializableProgramAccountCount {
+    pub program: Vec<u8>,
+    pub accounts: u64,
+}
+
+impl From<ProgramAccountCount> for SerializableProgramAccountCount {
+    fn from(x: ProgramAccountCount) -> Self {
+        Self {
+            program: x.program,
+            accounts: x.accounts,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SerializableEndOfStartupEvent {
+    pub slot: u64,
+    pub total_accounts: u64,
+    pub program_account_counts: Vec<SerializableProgramAccountCount>,
+}
+
+impl From<EndOfStartupEvent> for SerializableEndOfStartupEvent {
+    fn from(x: EndOfStartupEvent) -> Self {
+        Self {
+            slot: x.slot,
+            total_accounts: x.total_accounts,
+            program_account_counts: x
+                .program_account_counts
+                .into_iter()
+                .map(SerializableProgramAccountCount::from)
+                .collect(),
+        }
+    }
+}
+
+// -----------------
+// AccountCreatedEvent
+// -----------------
+#[derive(Debug, Serialize)]
+pub struct SerializableAccountCreatedEvent {
+    pub slot: u64,
+    pub pubkey: Vec<u8>,
+    pub lamports: u64,
+    pub owner: Option<Vec<u8>>,
+    pub txn_signature: Vec<u8>,
+    pub transaction_index: Option<u64>,
+    pub account_index: u64,
+}
+
+impl From<AccountCreatedEvent> for SerializableAccountCreatedEvent {
+    fn from(x: AccountCreatedEvent) -> Self {
+        Self {
+            slot: x.slot,
+            pubkey: x.pubkey,
+            lamports: x.lamports,
+            owner: x.owner,
+            txn_signature: x.txn_signature,
+            transaction_index: x.transaction_index,
+            account_index: x.account_index,
+        }
+    }
+}
+
+// -----------------
+// MessageWrapper
+// -----------------
+
+/// JSON counterpart of the MessageWrapper, i.e. `{"account": {...}}` for an account update.
+/// Only built right before serializing, so the variant size difference doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerializableMessageWrapper {
+    Account(SerializableUpdateAccountEvent),
+    Slot(SerializableSlotStatusEvent),
+    Transaction(SerializableTransactionEvent),
+    BlockMetadata(SerializableBlockMetadataEvent),
+    Entry(SerializableEntryEvent),
+    EndOfStartup(SerializableEndOfStartupEvent),
+    AccountCreated(SerializableAccountCreatedEvent),
+}
@@ -0,0 +1,132 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Caps the number of messages and bytes sent per second by blocking the caller until the
+/// current one second window has room for another message.
+pub struct RateLimiter {
+    /// Maximum messages per second, `0` if unlimited.
+    max_messages_per_second: u64,
+    /// Maximum bytes per second, `0` if unlimited.
+    max_bytes_per_second: u64,
+    window: Mutex<Window>,
+}
+
+impl RateLimiter {
+    pub fn new(max_messages_per_second: u64, max_bytes_per_second: u64) -> Self {
+        Self {
+            max_messages_per_second,
+            max_bytes_per_second,
+            window: Mutex::new(Window::new(Instant::now())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_messages_per_second > 0 || self.max_bytes_per_second > 0
+    }
+
+    /// Blocks until a message of [bytes] can be sent without exceeding the limits and returns
+    /// how long it blocked.
+    pub fn acquire(&self, bytes: usize) -> Duration {
+        if !self.is_enabled() {
+            return Duration::ZERO;
+        }
+
+        let mut window = self.window.lock().expect("rate limiter mutex poisoned");
+        let delay = window.delay(
+            Instant::now(),
+            bytes as u64,
+            self.max_messages_per_second,
+            self.max_bytes_per_second,
+        );
+        if !delay.is_zero() {
+            thread::sleep(delay);
+            *window = Window::new(Instant::now());
+        }
+        window.messages += 1;
+        window.bytes += bytes as u64;
+        delay
+    }
+}
+
+struct Window {
+    start: Instant,
+    messages: u64,
+    bytes: u64,
+}
+
+impl Window {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            messages: 0,
+            bytes: 0,
+        }
+    }
+
+    /// Returns how long to wait until a message of [bytes] fits into the window, starting a
+    /// new window at [now] if the current one elapsed.
+    /// A message exceeding [max_bytes] on its own is let through once the window is empty.
+    fn delay(&mut self, now: Instant, bytes: u64, max_messages: u64, max_bytes: u64) -> Duration {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= WINDOW {
+            *self = Window::new(now);
+            return Duration::ZERO;
+        }
+
+        let exceeds_messages = max_messages > 0 && self.messages + 1 > max_messages;
+        let exceeds_bytes = max_bytes > 0 && self.bytes > 0 && self.bytes + bytes > max_bytes;
+        if exceeds_messages || exceeds_bytes {
+            WINDOW - elapsed
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_once_messages_exceeded() {
+        let start = Instant::now();
+        let mut window = Window::new(start);
+        window.messages = 2;
+
+        let now = start + Duration::from_millis(400);
+        assert_eq!(window.delay(now, 10, 3, 0), Duration::ZERO);
+        assert_eq!(window.delay(now, 10, 2, 0), Duration::from_millis(600));
+    }
+
+    #[test]
+    fn delays_once_bytes_exceeded() {
+        let start = Instant::now();
+        let mut window = Window::new(start);
+
+        let now = start + Duration::from_millis(250);
+        // A single oversized message is let through if the window is empty
+        assert_eq!(window.delay(now, 200, 0, 100), Duration::ZERO);
+        window.bytes = 60;
+        assert_eq!(window.delay(now, 40, 0, 100), Duration::ZERO);
+        assert_eq!(window.delay(now, 41, 0, 100), Duration::from_millis(750));
+    }
+
+    #[test]
+    fn starts_new_window_once_elapsed() {
+        let start = Instant::now();
+        let mut window = Window::new(start);
+        window.messages = 5;
+        window.bytes = 500;
+
+        let now = start + Duration::from_secs(1);
+        assert_eq!(window.delay(now, 100, 1, 100), Duration::ZERO);
+        assert_eq!(window.messages, 0);
+        assert_eq!(window.bytes, 0);
+        assert_eq!(window.start, now);
+    }
+}
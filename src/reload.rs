@@ -0,0 +1,556 @@
+use std::{
+    collections::HashMap,
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, RwLock},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use log::{error, info, warn};
+use serde_json::Value;
+use solana_geyser_plugin_interface::geyser_plugin_interface::Result as PluginResult;
+
+use crate::{
+    commitment::CommitmentBuffer,
+    compaction::AccountCompactor,
+    prom::{
+        CONFIG_LAST_RELOAD_SUCCESS, CONFIG_LAST_RELOAD_TIMESTAMP_SECONDS, CONFIG_RELOADS_TOTAL,
+        REMOVED_ENV_DISCARDED_EVENTS_TOTAL,
+    },
+    publisher::Publisher,
+    startup::StartupProgress,
+    Config, KafkaPlugin,
+};
+
+/// Time to wait before checking again whether the replaced publishers are still in use, it
+/// doubles with each check up to [RELEASE_MAX_RETRY_INTERVAL].
+const RELEASE_RETRY_INTERVAL: Duration = Duration::from_millis(10);
+const RELEASE_MAX_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+/// Time after which a warning is logged if the replaced publishers are still in use.
+const RELEASE_WARN_AFTER: Duration = Duration::from_secs(60);
+
+/// The publishers of all environments. They are replaced as a whole when the config is
+/// reloaded, so that each validator callback uses either the old or the new publishers.
+/// The publishers of unchanged environments are shared by both.
+pub type SharedPublishers = Arc<RwLock<Option<Arc<Vec<Arc<Publisher>>>>>>;
+
+/// Plugin state kept per environment, which follows the environments on reload.
+#[derive(Default)]
+pub struct EnvState {
+    pub startup_progress: Arc<StartupProgress>,
+    pub commitment_buffer: Arc<CommitmentBuffer>,
+    pub account_compactor: Arc<AccountCompactor>,
+}
+
+// -----------------
+// ConfigWatcher
+// -----------------
+
+/// Checks the config file for changes in an interval and replaces the publishers once it
+/// changed.
+pub struct ConfigWatcher {
+    stopped: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    /// Watches the file at [config_path] that [config] was loaded from.
+    pub fn new(
+        config_path: PathBuf,
+        config: &Config,
+        publishers: SharedPublishers,
+        state: EnvState,
+        interval: Duration,
+    ) -> Self {
+        let stopped = Arc::new((Mutex::new(false), Condvar::new()));
+        let thread = {
+            let stopped = stopped.clone();
+            let mut reloader = Reloader::new(config_path, config, publishers, state);
+            thread::Builder::new()
+                .name("config-watcher".to_string())
+                .spawn(move || {
+                    let (lock, cvar) = &*stopped;
+                    let mut is_stopped = lock.lock().expect("config watcher mutex poisoned");
+                    while !*is_stopped {
+                        is_stopped = cvar
+                            .wait_timeout(is_stopped, interval)
+                            .expect("config watcher mutex poisoned")
+                            .0;
+                        if !*is_stopped {
+                            reloader.reload_if_modified();
+                        }
+                    }
+                })
+                .expect("failed to spawn config watcher thread")
+        };
+        Self {
+            stopped,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.stopped;
+        *lock.lock().expect("config watcher mutex poisoned") = true;
+        cvar.notify_all();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Config watcher thread panicked");
+            }
+        }
+    }
+}
+
+// -----------------
+// LoadOnlySettings
+// -----------------
+
+/// Settings outside the environments that are only applied when the plugin is loaded.
+struct LoadOnlySettings {
+    prometheus: Option<SocketAddr>,
+    owner_cache_capacity: usize,
+    reload_interval_secs: u64,
+}
+
+impl LoadOnlySettings {
+    fn new(config: &Config) -> Self {
+        Self {
+            prometheus: config.prometheus,
+            owner_cache_capacity: config.owner_cache_capacity,
+            reload_interval_secs: config.reload_interval_secs,
+        }
+    }
+
+    /// Returns the keys whose values differ in [other].
+    fn changed(&self, other: &Self) -> Vec<&'static str> {
+        [
+            ("prometheus", self.prometheus != other.prometheus),
+            (
+                "owner_cache_capacity",
+                self.owner_cache_capacity != other.owner_cache_capacity,
+            ),
+            (
+                "reload_interval_secs",
+                self.reload_interval_secs != other.reload_interval_secs,
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(key, _)| key)
+        .collect()
+    }
+}
+
+// -----------------
+// Reloader
+// -----------------
+struct Reloader {
+    config_path: PathBuf,
+    /// The settings the plugin was loaded with, changes of them are ignored on reload.
+    settings: LoadOnlySettings,
+    /// The configs the current publishers were created from, keyed by environment name.
+    env_configs: HashMap<String, Option<Value>>,
+    publishers: SharedPublishers,
+    state: EnvState,
+    last_modified: Option<SystemTime>,
+}
+
+impl Reloader {
+    fn new(
+        config_path: PathBuf,
+        config: &Config,
+        publishers: SharedPublishers,
+        state: EnvState,
+    ) -> Self {
+        let last_modified = Self::modified(&config_path);
+        Self {
+            config_path,
+            settings: LoadOnlySettings::new(config),
+            env_configs: Self::env_configs(config),
+            publishers,
+            state,
+            last_modified,
+        }
+    }
+
+    /// Returns the config of each environment of [config] along with the global settings it
+    /// falls back to, `None` if it can't be serialized and thus not be compared.
+    fn env_configs(config: &Config) -> HashMap<String, Option<Value>> {
+        let global = serde_json::to_value(config).ok().map(|mut global| {
+            if let Some(global) = global.as_object_mut() {
+                global.remove("environments");
+            }
+            global
+        });
+        config
+            .environments
+            .iter()
+            .map(|env_config| {
+                let env = serde_json::to_value(env_config).ok();
+                let value = global
+                    .clone()
+                    .zip(env)
+                    .map(|(global, env)| Value::Array(vec![global, env]));
+                (env_config.name().to_string(), value)
+            })
+            .collect()
+    }
+
+    /// Creates the publishers of [config], reusing the current publishers of environments
+    /// whose config is unchanged.
+    fn create_publishers(
+        &self,
+        config: &Config,
+        env_configs: &HashMap<String, Option<Value>>,
+    ) -> PluginResult<Vec<Arc<Publisher>>> {
+        let current = self
+            .publishers
+            .read()
+            .expect("publishers lock poisoned")
+            .clone()
+            .unwrap_or_default();
+        config
+            .environments
+            .iter()
+            .map(|env_config| {
+                let env = env_config.name();
+                let unchanged = match (self.env_configs.get(env), env_configs.get(env)) {
+                    (Some(Some(current)), Some(Some(reloaded))) => current == reloaded,
+                    _ => false,
+                };
+                match current.iter().find(|p| unchanged && p.env() == env) {
+                    Some(publisher) => Ok(publisher.clone()),
+                    None => KafkaPlugin::create_publisher(
+                        config,
+                        env_config,
+                        &self.state.startup_progress,
+                    )
+                    .map(Arc::new),
+                }
+            })
+            .collect()
+    }
+
+    /// Discards the buffered events of the environments that were removed.
+    fn discard_removed(&self, env_configs: &HashMap<String, Option<Value>>) {
+        for env in self.env_configs.keys() {
+            if env_configs.contains_key(env) {
+                continue;
+            }
+            let discarded = self.state.commitment_buffer.discard_env(env)
+                + self.state.account_compactor.discard_env(env);
+            if discarded > 0 {
+                warn!(
+                    "Discarded {} buffered events of the removed {} environment",
+                    discarded, env
+                );
+            }
+            REMOVED_ENV_DISCARDED_EVENTS_TOTAL
+                .with_label_values(&[env])
+                .inc_by(discarded as u64);
+        }
+    }
+
+    fn modified(config_path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(config_path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Reloads the config if the file was modified since it was last loaded and returns
+    /// whether the publishers were replaced.
+    fn reload_if_modified(&mut self) -> bool {
+        let modified = Self::modified(&self.config_path);
+        if modified.is_none() || modified == self.last_modified {
+            return false;
+        }
+        self.last_modified = modified;
+
+        let result = Config::read_from(&self.config_path)
+            .map_err(|err| err.to_string())
+            .and_then(|config| {
+                config
+                    .validate_on_load()
+                    .map_err(|errors| errors.join(" | "))?;
+                let env_configs = Self::env_configs(&config);
+                let publishers = self
+                    .create_publishers(&config, &env_configs)
+                    .map_err(|err| err.to_string())?;
+                Ok((publishers, env_configs, LoadOnlySettings::new(&config)))
+            });
+        let reloaded = match result {
+            Ok((publishers, env_configs, settings)) => {
+                for key in self.settings.changed(&settings) {
+                    warn!(
+                        "Ignoring the changed {} in {:?}, it requires a restart",
+                        key, self.config_path
+                    );
+                }
+                info!(
+                    "Reloaded config from {:?} with {} environments",
+                    self.config_path,
+                    publishers.len()
+                );
+                // Added environments are tracked before their publishers record any accounts
+                self.state
+                    .startup_progress
+                    .set_envs(env_configs.keys().map(String::as_str));
+                let replaced = self
+                    .publishers
+                    .write()
+                    .expect("publishers lock poisoned")
+                    .replace(Arc::new(publishers));
+                if let Some(replaced) = replaced {
+                    Self::release(replaced);
+                }
+                self.discard_removed(&env_configs);
+                self.env_configs = env_configs;
+                true
+            }
+            Err(err) => {
+                error!(
+                    "Failed to reload config from {:?}, keeping the current one: {}",
+                    self.config_path, err
+                );
+                false
+            }
+        };
+
+        CONFIG_RELOADS_TOTAL
+            .with_label_values(&[if reloaded { "success" } else { "failed" }])
+            .inc();
+        CONFIG_LAST_RELOAD_SUCCESS.set(reloaded as i64);
+        CONFIG_LAST_RELOAD_TIMESTAMP_SECONDS.set(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs() as i64),
+        );
+        reloaded
+    }
+
+    /// Waits until validator callbacks no longer use the [replaced] publishers and drops them,
+    /// which publishes the queued and in-flight events of those that were not reused. This
+    /// happens on a separate thread so that a stalled publisher call does not hold up later
+    /// reloads.
+    fn release(replaced: Arc<Vec<Arc<Publisher>>>) -> JoinHandle<()> {
+        thread::Builder::new()
+            .name("release-publishers".to_string())
+            .spawn(move || {
+                let started = Instant::now();
+                let mut replaced = replaced;
+                let mut retry_interval = RELEASE_RETRY_INTERVAL;
+                let mut warned = false;
+                loop {
+                    match Arc::try_unwrap(replaced) {
+                        Ok(publishers) => {
+                            drop(publishers);
+                            return;
+                        }
+                        Err(publishers) => replaced = publishers,
+                    }
+                    if !warned && started.elapsed() >= RELEASE_WARN_AFTER {
+                        warn!(
+                            "Replaced publishers are still in use after {:?}",
+                            RELEASE_WARN_AFTER
+                        );
+                        warned = true;
+                    }
+                    thread::sleep(retry_interval);
+                    retry_interval = (retry_interval * 2).min(RELEASE_MAX_RETRY_INTERVAL);
+                }
+            })
+            .expect("failed to spawn release publishers thread")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{commitment::BufferedEvent, Commitment, UpdateAccountEvent};
+
+    fn write_config(path: &PathBuf, envs: &[&str]) {
+        write_config_with_url(path, envs, "http://localhost:9999");
+    }
+
+    fn write_config_with_url(path: &PathBuf, envs: &[&str], url: &str) {
+        let environments = envs
+            .iter()
+            .map(|env| format!(r#"{{ "name": "{env}", "url": "{url}" }}"#))
+            .collect::<Vec<_>>()
+            .join(",");
+        fs::write(path, format!(r#"{{ "environments": [{environments}] }}"#)).unwrap();
+    }
+
+    fn envs(publishers: &SharedPublishers) -> Vec<String> {
+        publishers
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|p| p.env().to_string())
+            .collect()
+    }
+
+    fn publisher(publishers: &SharedPublishers, env: &str) -> Arc<Publisher> {
+        publishers
+            .read()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|p| p.env() == env)
+            .unwrap()
+            .clone()
+    }
+
+    #[test]
+    fn reloads_modified_config() {
+        let path = std::env::temp_dir().join(format!("geyser-kafka-reload-{}", std::process::id()));
+        write_config(&path, &["dev"]);
+        let config = Config::read_from(&path).unwrap();
+        let publishers: SharedPublishers = Arc::new(RwLock::new(Some(Arc::new(
+            KafkaPlugin::create_publishers(&config, &Arc::default()).unwrap(),
+        ))));
+        let mut reloader = Reloader::new(
+            path.clone(),
+            &config,
+            publishers.clone(),
+            EnvState::default(),
+        );
+        assert!(!reloader.reload_if_modified());
+
+        // Publishers still in use are replaced, but released only once they are unused
+        let in_use = publishers.read().unwrap().clone();
+        write_config(&path, &["dev", "stage"]);
+        reloader.last_modified = Some(UNIX_EPOCH);
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            drop(in_use);
+        });
+        assert!(reloader.reload_if_modified());
+        handle.join().unwrap();
+        assert_eq!(envs(&publishers), vec!["dev", "stage"]);
+
+        // Invalid configs keep the current publishers
+        fs::write(
+            &path,
+            r#"{ "strict_config": true, "environments": [{ "name": "dev", "url": "" }] }"#,
+        )
+        .unwrap();
+        reloader.last_modified = Some(UNIX_EPOCH);
+        assert!(!reloader.reload_if_modified());
+        assert_eq!(envs(&publishers), vec!["dev", "stage"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reuses_publishers_of_unchanged_envs() {
+        let path = std::env::temp_dir().join(format!("geyser-kafka-reuse-{}", std::process::id()));
+        write_config(&path, &["dev", "stage"]);
+        let config = Config::read_from(&path).unwrap();
+        let publishers: SharedPublishers = Arc::new(RwLock::new(Some(Arc::new(
+            KafkaPlugin::create_publishers(&config, &Arc::default()).unwrap(),
+        ))));
+        let state = EnvState::default();
+        state.startup_progress.set_envs(["dev", "stage"]);
+        let mut reloader = Reloader::new(
+            path.clone(),
+            &config,
+            publishers.clone(),
+            EnvState {
+                startup_progress: state.startup_progress.clone(),
+                commitment_buffer: state.commitment_buffer.clone(),
+                account_compactor: state.account_compactor.clone(),
+            },
+        );
+        let dev = publisher(&publishers, "dev");
+        let stage = publisher(&publishers, "stage");
+
+        // Unchanged environments keep their publishers
+        write_config(&path, &["dev", "stage", "prod"]);
+        reloader.last_modified = Some(UNIX_EPOCH);
+        assert!(reloader.reload_if_modified());
+        assert!(Arc::ptr_eq(&dev, &publisher(&publishers, "dev")));
+        assert!(Arc::ptr_eq(&stage, &publisher(&publishers, "stage")));
+
+        // Changing the global settings recreates all of them
+        fs::write(
+            &path,
+            r#"{ "wrap_messages": true, "environments": [{ "name": "dev", "url": "http://localhost:9999" }, { "name": "stage", "url": "http://localhost:9999" }] }"#,
+        )
+        .unwrap();
+        reloader.last_modified = Some(UNIX_EPOCH);
+        assert!(reloader.reload_if_modified());
+        assert!(!Arc::ptr_eq(&dev, &publisher(&publishers, "dev")));
+        assert!(!Arc::ptr_eq(&stage, &publisher(&publishers, "stage")));
+        let dev = publisher(&publishers, "dev");
+
+        // The buffered events of removed environments are discarded, added ones are tracked
+        state.commitment_buffer.push(
+            1,
+            Commitment::Confirmed,
+            "stage",
+            BufferedEvent::Account(UpdateAccountEvent::default()),
+        );
+        state
+            .account_compactor
+            .push("stage", UpdateAccountEvent::default());
+        write_config_with_url(&path, &["dev", "prod"], "http://localhost:9998");
+        reloader.last_modified = Some(UNIX_EPOCH);
+        assert!(reloader.reload_if_modified());
+        assert!(!Arc::ptr_eq(&dev, &publisher(&publishers, "dev")));
+        assert_eq!(state.commitment_buffer.discard_env("stage"), 0);
+        assert_eq!(state.account_compactor.discard_env("stage"), 0);
+        state.startup_progress.record("prod", &[1; 32], 1);
+        assert_eq!(
+            state
+                .startup_progress
+                .end_of_startup_event("prod")
+                .total_accounts,
+            1
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn releases_unused_publishers() {
+        let path =
+            std::env::temp_dir().join(format!("geyser-kafka-release-{}", std::process::id()));
+        write_config(&path, &["dev"]);
+        let config = Config::read_from(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let replaced = Arc::new(KafkaPlugin::create_publishers(&config, &Arc::default()).unwrap());
+        let in_use = replaced.clone();
+        let handle = Reloader::release(replaced);
+        thread::sleep(Duration::from_millis(50));
+        assert!(!handle.is_finished());
+        drop(in_use);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn load_only_settings() {
+        let config = Config::read_from("test/fixtures/configs/multi-env-typed.json").unwrap();
+        let mut reloaded = Config::read_from("test/fixtures/configs/multi-env-typed.json").unwrap();
+        let settings = LoadOnlySettings::new(&config);
+        assert!(settings
+            .changed(&LoadOnlySettings::new(&reloaded))
+            .is_empty());
+
+        reloaded.owner_cache_capacity += 1;
+        reloaded.prometheus = Some("127.0.0.1:9999".parse().unwrap());
+        assert_eq!(
+            settings.changed(&LoadOnlySettings::new(&reloaded)),
+            vec!["prometheus", "owner_cache_capacity"]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+/// Tracks the parent of each slot the validator reported since the last root, which allows
+/// telling whether a slot is on the same fork as another one.
+#[derive(Debug, Default)]
+pub struct SlotTree {
+    parents: BTreeMap<u64, u64>,
+}
+
+impl SlotTree {
+    pub fn insert(&mut self, slot: u64, parent: u64) {
+        self.parents.insert(slot, parent);
+    }
+
+    pub fn parent(&self, slot: u64) -> Option<u64> {
+        self.parents.get(&slot).copied()
+    }
+
+    /// Returns `Some(true)` if [ancestor] is [slot] or one of its ancestors and `Some(false)`
+    /// if it is not.
+    /// Returns `None` if that cannot be determined since the parent of a slot in between is
+    /// unknown.
+    pub fn is_ancestor_or_self(&self, ancestor: u64, slot: u64) -> Option<bool> {
+        let mut current = slot;
+        loop {
+            if current == ancestor {
+                return Some(true);
+            }
+            if current < ancestor {
+                return Some(false);
+            }
+            current = self.parent(current)?;
+        }
+    }
+
+    /// Marks [root] as rooted and returns the slots, paired with their parents, that can no
+    /// longer be rooted since they are neither ancestors nor descendants of [root], in slot
+    /// order.
+    ///
+    /// Those dead slots as well as all slots older than [root] are forgotten since every slot
+    /// processed from now on descends from [root].
+    /// Slots whose ancestry is unknown are not considered dead.
+    pub fn root(&mut self, root: u64) -> Vec<(u64, u64)> {
+        let dead = self
+            .parents
+            .iter()
+            .map(|(slot, parent)| (*slot, *parent))
+            .filter(|(slot, _)| {
+                self.is_ancestor_or_self(*slot, root) == Some(false)
+                    && self.is_ancestor_or_self(root, *slot) == Some(false)
+            })
+            .collect::<Vec<_>>();
+        for (slot, _) in &dead {
+            self.parents.remove(slot);
+        }
+        self.prune(root);
+        dead
+    }
+
+    /// Forgets all slots older than [root] since every slot processed from now on descends
+    /// from it.
+    pub fn prune(&mut self, root: u64) {
+        self.parents = self.parents.split_off(&root);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //      1
+    //     / \
+    //    2   3
+    //    |   |
+    //    4   5
+    fn forked_tree() -> SlotTree {
+        let mut tree = SlotTree::default();
+        tree.insert(2, 1);
+        tree.insert(3, 1);
+        tree.insert(4, 2);
+        tree.insert(5, 3);
+        tree
+    }
+
+    #[test]
+    fn is_ancestor_or_self() {
+        let tree = forked_tree();
+        assert_eq!(tree.is_ancestor_or_self(4, 4), Some(true));
+        assert_eq!(tree.is_ancestor_or_self(2, 4), Some(true));
+        assert_eq!(tree.is_ancestor_or_self(1, 4), Some(true));
+        assert_eq!(tree.is_ancestor_or_self(3, 4), Some(false));
+        assert_eq!(tree.is_ancestor_or_self(2, 5), Some(false));
+        assert_eq!(tree.is_ancestor_or_self(5, 4), Some(false));
+        // The parent of 1 is unknown
+        assert_eq!(tree.is_ancestor_or_self(0, 4), None);
+    }
+
+    #[test]
+    fn root_returns_dead_slots() {
+        let mut tree = forked_tree();
+        tree.insert(6, 5);
+        tree.insert(7, 4);
+
+        assert_eq!(tree.root(4), vec![(3, 1), (5, 3), (6, 5)]);
+        assert_eq!(tree.parent(5), None);
+        assert_eq!(tree.parent(6), None);
+        assert_eq!(tree.parent(7), Some(4));
+        // Dead slots are only reported once
+        tree.insert(8, 7);
+        assert!(tree.root(7).is_empty());
+    }
+
+    #[test]
+    fn prune() {
+        let mut tree = forked_tree();
+        tree.prune(3);
+        assert_eq!(tree.parent(2), None);
+        assert_eq!(tree.parent(4), Some(2));
+        assert_eq!(tree.parent(3), Some(1));
+        assert_eq!(tree.is_ancestor_or_self(3, 5), Some(true));
+    }
+}
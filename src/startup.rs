@@ -0,0 +1,159 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, RwLock,
+    },
+};
+
+use crate::{
+    prom::{STARTUP_ACCOUNTS, STARTUP_COMPLETE},
+    EndOfStartupEvent, ProgramAccountCount,
+};
+
+/// Startup account counts by owner program.
+type ProgramCounts = Mutex<HashMap<Vec<u8>, u64>>;
+
+/// Tracks the accounts that were published to each environment while the validator streams
+/// its accounts on startup.
+#[derive(Default)]
+pub struct StartupProgress {
+    /// The counts of each environment, keyed by its name.
+    counts: RwLock<HashMap<String, ProgramCounts>>,
+    /// The highest slot seen for the startup accounts.
+    slot: AtomicU64,
+}
+
+impl StartupProgress {
+    pub fn new<'a>(envs: impl IntoIterator<Item = &'a str>) -> Self {
+        STARTUP_COMPLETE.set(0);
+        Self {
+            counts: RwLock::new(
+                envs.into_iter()
+                    .map(|env| (env.to_string(), Mutex::default()))
+                    .collect(),
+            ),
+            slot: AtomicU64::default(),
+        }
+    }
+
+    /// Tracks [envs] from now on, keeping the counts of those that were tracked already.
+    pub fn set_envs<'a>(&self, envs: impl IntoIterator<Item = &'a str>) {
+        let mut counts = self.counts.write().expect("startup counts lock poisoned");
+        let mut previous = std::mem::take(&mut *counts);
+        *counts = envs
+            .into_iter()
+            .map(|env| (env.to_string(), previous.remove(env).unwrap_or_default()))
+            .collect();
+    }
+
+    /// Records that a startup account owned by [owner] was published to [env].
+    pub fn record(&self, env: &str, owner: &[u8], slot: u64) {
+        self.slot.fetch_max(slot, Ordering::Relaxed);
+        if let Some(counts) = self
+            .counts
+            .read()
+            .expect("startup counts lock poisoned")
+            .get(env)
+        {
+            let mut counts = counts.lock().expect("startup counts mutex poisoned");
+            *counts.entry(owner.to_vec()).or_default() += 1;
+        }
+        STARTUP_ACCOUNTS.with_label_values(&[env]).inc();
+    }
+
+    /// Builds the event marking the end of startup for [env].
+    pub fn end_of_startup_event(&self, env: &str) -> EndOfStartupEvent {
+        let mut program_account_counts = match self
+            .counts
+            .read()
+            .expect("startup counts lock poisoned")
+            .get(env)
+        {
+            Some(counts) => counts
+                .lock()
+                .expect("startup counts mutex poisoned")
+                .iter()
+                .map(|(program, accounts)| ProgramAccountCount {
+                    program: program.clone(),
+                    accounts: *accounts,
+                })
+                .collect::<Vec<_>>(),
+            None => vec![],
+        };
+        program_account_counts.sort_by(|a, b| a.program.cmp(&b.program));
+
+        EndOfStartupEvent {
+            slot: self.slot.load(Ordering::Relaxed),
+            total_accounts: program_account_counts.iter().map(|c| c.accounts).sum(),
+            program_account_counts,
+        }
+    }
+
+    pub fn complete(&self) {
+        STARTUP_COMPLETE.set(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn end_of_startup_event_counts_per_env_and_program() {
+        let progress = StartupProgress::new(["dev", "stage"]);
+        let program_a = vec![1u8; 32];
+        let program_b = vec![2u8; 32];
+
+        progress.record("dev", &program_b, 10);
+        progress.record("dev", &program_a, 12);
+        progress.record("dev", &program_b, 11);
+        progress.record("stage", &program_a, 12);
+
+        let dev = progress.end_of_startup_event("dev");
+        assert_eq!(dev.slot, 12);
+        assert_eq!(dev.total_accounts, 3);
+        assert_eq!(
+            dev.program_account_counts,
+            vec![
+                ProgramAccountCount {
+                    program: program_a.clone(),
+                    accounts: 1,
+                },
+                ProgramAccountCount {
+                    program: program_b,
+                    accounts: 2,
+                },
+            ]
+        );
+
+        let stage = progress.end_of_startup_event("stage");
+        assert_eq!(stage.total_accounts, 1);
+        assert_eq!(
+            stage.program_account_counts,
+            vec![ProgramAccountCount {
+                program: program_a,
+                accounts: 1,
+            }]
+        );
+
+        let unknown = progress.end_of_startup_event("unknown");
+        assert_eq!(unknown.total_accounts, 0);
+        assert!(unknown.program_account_counts.is_empty());
+    }
+
+    #[test]
+    fn set_envs_keeps_counts_of_tracked_envs() {
+        let progress = StartupProgress::new(["dev", "stage"]);
+        let program = vec![1u8; 32];
+        progress.record("dev", &program, 10);
+        progress.record("stage", &program, 10);
+        progress.record("prod", &program, 10);
+
+        progress.set_envs(["dev", "prod"]);
+        progress.record("prod", &program, 11);
+        assert_eq!(progress.end_of_startup_event("dev").total_accounts, 1);
+        assert_eq!(progress.end_of_startup_event("stage").total_accounts, 0);
+        assert_eq!(progress.end_of_startup_event("prod").total_accounts, 1);
+    }
+}
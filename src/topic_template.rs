@@ -0,0 +1,229 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::Cluster;
+
+const CLUSTER: &str = "{cluster}";
+const EVENT: &str = "{event}";
+const OWNER: &str = "{owner}";
+const OWNER_LABEL: &str = "{owner_label}";
+const PLACEHOLDERS: &[&str] = &[CLUSTER, EVENT, OWNER, OWNER_LABEL];
+
+/// Expanded in place of [OWNER] and [OWNER_LABEL] for events whose owner is not known.
+const UNKNOWN_OWNER: &str = "unknown";
+
+/// Maximum number of owners whose topic is cached per template, the owner cached first is
+/// evicted once exceeded.
+const RESOLVED_TOPICS_CAPACITY: usize = 10_000;
+
+/// Checks that [template] only contains known placeholders and returns whether it depends on
+/// the owner of an account.
+pub(crate) fn parse(template: &str) -> Result<bool, String> {
+    let mut per_owner = false;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            return Err("unterminated {".to_string());
+        };
+        let placeholder = &rest[start..start + len + 1];
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!(
+                "unknown placeholder {placeholder}, expected one of {}",
+                PLACEHOLDERS.join(", ")
+            ));
+        }
+        per_owner |= placeholder == OWNER || placeholder == OWNER_LABEL;
+        rest = &rest[start + len + 1..];
+    }
+    Ok(per_owner)
+}
+
+/// Returns [template] without its placeholders.
+pub(crate) fn strip(template: &str) -> String {
+    PLACEHOLDERS
+        .iter()
+        .fold(template.to_string(), |topic, placeholder| {
+            topic.replace(placeholder, "")
+        })
+}
+
+/// Expands the placeholders of [template] that are the same for all events of a topic.
+pub(crate) fn expand(template: &str, cluster: &Cluster, event: &str) -> String {
+    template
+        .replace(CLUSTER, cluster.name())
+        .replace(EVENT, event)
+}
+
+/// Topic whose `{owner}` and `{owner_label}` placeholders are expanded per event.
+pub(crate) struct TopicTemplate {
+    topic: Arc<str>,
+    per_owner: Option<Box<PerOwnerTopics>>,
+}
+
+/// The topics resolved for each owner are cached.
+struct PerOwnerTopics {
+    owner_labels: Arc<HashMap<Vec<u8>, String>>,
+    resolved: RwLock<ResolvedTopics>,
+}
+
+#[derive(Default)]
+struct ResolvedTopics {
+    topics: HashMap<Option<Pubkey>, Arc<str>>,
+    insertion_order: VecDeque<Option<Pubkey>>,
+}
+
+impl TopicTemplate {
+    pub fn new(
+        template: &str,
+        cluster: &Cluster,
+        event: &str,
+        owner_labels: Arc<HashMap<Vec<u8>, String>>,
+    ) -> Self {
+        let topic = expand(template, cluster, event);
+        let per_owner = (topic.contains(OWNER) || topic.contains(OWNER_LABEL)).then(|| {
+            Box::new(PerOwnerTopics {
+                owner_labels,
+                resolved: RwLock::default(),
+            })
+        });
+        Self {
+            topic: topic.into(),
+            per_owner,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.topic.is_empty()
+    }
+
+    /// Returns the topic for events of accounts owned by [owner]. Owners without a label in
+    /// [owner_labels] are expanded as `{owner}` in place of `{owner_label}`.
+    pub fn for_owner(&self, owner: Option<&[u8]>) -> Arc<str> {
+        let Some(per_owner) = &self.per_owner else {
+            return self.topic.clone();
+        };
+        let key = owner.and_then(|owner| Pubkey::try_from(owner).ok());
+        if let Some(topic) = per_owner
+            .resolved
+            .read()
+            .expect("topic template lock poisoned")
+            .topics
+            .get(&key)
+        {
+            return topic.clone();
+        }
+
+        let owner_name = key.map_or_else(|| UNKNOWN_OWNER.to_string(), |owner| owner.to_string());
+        let owner_label = key
+            .and_then(|owner| per_owner.owner_labels.get(owner.as_ref()))
+            .unwrap_or(&owner_name);
+        let topic: Arc<str> = self
+            .topic
+            .replace(OWNER_LABEL, owner_label)
+            .replace(OWNER, &owner_name)
+            .into();
+
+        let mut resolved = per_owner
+            .resolved
+            .write()
+            .expect("topic template lock poisoned");
+        if resolved.topics.insert(key, topic.clone()).is_none() {
+            resolved.insertion_order.push_back(key);
+        }
+        while resolved.topics.len() > RESOLVED_TOPICS_CAPACITY {
+            match resolved.insertion_order.pop_front() {
+                Some(evicted) => resolved.topics.remove(&evicted),
+                None => break,
+            };
+        }
+        topic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+    const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+
+    #[test]
+    fn parse_placeholders() {
+        assert_eq!(parse("geyser.mainnet.account_update"), Ok(false));
+        assert_eq!(parse("geyser.{cluster}.{event}"), Ok(false));
+        assert_eq!(parse("geyser.{cluster}.{owner_label}"), Ok(true));
+        assert_eq!(
+            parse("geyser.{cluster}.{program}"),
+            Err(
+                "unknown placeholder {program}, expected one of {cluster}, {event}, \
+                 {owner}, {owner_label}"
+                    .to_string()
+            )
+        );
+        assert_eq!(parse("geyser.{cluster"), Err("unterminated {".to_string()));
+        assert_eq!(strip("geyser.{cluster}.{owner}"), "geyser..");
+    }
+
+    #[test]
+    fn expand_per_owner() {
+        let token_program = Pubkey::from_str(TOKEN_PROGRAM).unwrap().to_bytes();
+        let token_2022_program = Pubkey::from_str(TOKEN_2022_PROGRAM).unwrap().to_bytes();
+        let owner_labels = Arc::new(HashMap::from([(
+            token_program.to_vec(),
+            "spl_token".to_string(),
+        )]));
+
+        let template = TopicTemplate::new(
+            "geyser.{cluster}.{event}.{owner_label}",
+            &Cluster::Devnet,
+            "account_update",
+            owner_labels.clone(),
+        );
+        assert_eq!(
+            &*template.for_owner(Some(&token_program)),
+            "geyser.devnet.account_update.spl_token"
+        );
+        assert_eq!(
+            *template.for_owner(Some(&token_2022_program)),
+            format!("geyser.devnet.account_update.{TOKEN_2022_PROGRAM}")
+        );
+        assert_eq!(
+            &*template.for_owner(None),
+            "geyser.devnet.account_update.unknown"
+        );
+        assert_eq!(
+            template
+                .per_owner
+                .as_ref()
+                .unwrap()
+                .resolved
+                .read()
+                .unwrap()
+                .topics
+                .len(),
+            3
+        );
+        // Cached topics are shared instead of copied
+        assert!(Arc::ptr_eq(
+            &template.for_owner(Some(&token_program)),
+            &template.for_owner(Some(&token_program))
+        ));
+
+        let template = TopicTemplate::new(
+            "geyser.{cluster}.{owner}",
+            &Cluster::Custom("localnet".to_string()),
+            "account_update",
+            owner_labels,
+        );
+        assert_eq!(
+            *template.for_owner(Some(&token_program)),
+            format!("geyser.localnet.{TOKEN_PROGRAM}")
+        );
+    }
+}
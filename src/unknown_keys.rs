@@ -0,0 +1,172 @@
+use serde::{
+    de::{self, value, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    env_config::{EnvConfigKafka, EnvConfigLocal},
+    Config, EnvConfig,
+};
+
+/// Deprecated keys that are still accepted in place of the current ones.
+const DEPRECATED_ALIASES: &[(&str, &str)] = &[
+    ("update_slot_topic", "slot_status_topic"),
+    ("update_transaction_topic", "transaction_topic"),
+];
+
+/// Keys of the config that are read by the validator instead of the plugin.
+const VALIDATOR_KEYS: &[&str] = &["libpath"];
+
+/// Keys of the config that are ignored or deprecated, see [check_keys].
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct KeyWarnings {
+    pub unknown: Vec<String>,
+    pub deprecated: Vec<String>,
+}
+
+/// Finds the keys of the config [value] and its environments that are not fields of [Config]
+/// or the respective [EnvConfig] and thus would be ignored, as well as deprecated aliases.
+pub(crate) fn check_keys(value: &Value) -> KeyWarnings {
+    let mut warnings = KeyWarnings::default();
+    let Value::Object(config) = value else {
+        return warnings;
+    };
+    check_object(config, "", struct_fields::<Config>(), &mut warnings);
+
+    let Some(Value::Array(environments)) = config.get("environments") else {
+        return warnings;
+    };
+    for (idx, env) in environments.iter().enumerate() {
+        let Value::Object(env) = env else {
+            continue;
+        };
+        let env_type = match env.get("type") {
+            Some(Value::String(env_type)) => env_type.as_str(),
+            Some(_) => continue,
+            None => match EnvConfig::untagged_type(env) {
+                Some(env_type) => env_type,
+                None => continue,
+            },
+        };
+        // Invalid types are rejected when the environment is deserialized
+        let fields = match env_type {
+            "kafka" => struct_fields::<EnvConfigKafka>(),
+            "local" => struct_fields::<EnvConfigLocal>(),
+            _ => continue,
+        };
+        check_object(env, &format!("environments[{idx}]."), fields, &mut warnings);
+    }
+    warnings
+}
+
+fn check_object(
+    object: &Map<String, Value>,
+    path: &str,
+    fields: &[&str],
+    warnings: &mut KeyWarnings,
+) {
+    let is_env = !path.is_empty();
+    for key in object.keys() {
+        if let Some((_, field)) = DEPRECATED_ALIASES.iter().find(|(alias, _)| alias == key) {
+            warnings
+                .deprecated
+                .push(format!("{path}{key} is deprecated, use {field} instead"));
+        } else if !(fields.contains(&key.as_str())
+            || is_env && key == "type"
+            || !is_env && VALIDATOR_KEYS.contains(&key.as_str()))
+        {
+            warnings.unknown.push(format!("Unknown key {path}{key}"));
+        }
+    }
+}
+
+/// Returns the fields of the struct [T] as known to its [Deserialize] implementation.
+fn struct_fields<'de, T: Deserialize<'de>>() -> &'static [&'static str] {
+    let mut fields: &'static [&'static str] = &[];
+    let _ = T::deserialize(FieldsDeserializer {
+        fields: &mut fields,
+    });
+    fields
+}
+
+/// Deserializer that records the fields of the struct it is asked to deserialize and fails.
+struct FieldsDeserializer<'a> {
+    fields: &'a mut &'static [&'static str],
+}
+
+impl<'de, 'a> Deserializer<'de> for FieldsDeserializer<'a> {
+    type Error = value::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("expected a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.fields = fields;
+        Err(de::Error::custom("only the fields are recorded"))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        option unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier
+        ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_and_deprecated_keys() {
+        let (config, warnings) =
+            Config::read_with_warnings("test/fixtures/configs/unknown-keys.json")
+                .expect("should deserialize config");
+        assert_eq!(
+            warnings,
+            vec![
+                "Unknown key publish_all_acounts",
+                "Unknown key environments[0].program_allowlist_intervall",
+                "Unknown key environments[1].include_system_account",
+                "update_slot_topic is deprecated, use slot_status_topic instead",
+                "update_transaction_topic is deprecated, use transaction_topic instead",
+                "environments[1].update_slot_topic is deprecated, use slot_status_topic instead",
+            ]
+        );
+
+        // Deprecated keys are still applied
+        assert_eq!(config.slot_status_topic, "geyser.mainnet.slot_status");
+        assert_eq!(config.transaction_topic, "geyser.mainnet.transaction");
+        assert_eq!(
+            config.environments[1].topics(&config).slot_status_topic,
+            "local.slot_status"
+        );
+    }
+
+    #[test]
+    fn strict_config_rejects_unknown_keys() {
+        let err = Config::read_from("test/fixtures/configs/strict-unknown-keys.json")
+            .err()
+            .expect("should reject unknown keys")
+            .to_string();
+        assert!(err.ends_with(
+            "Unknown key publish_all_acounts | \
+             Unknown key environments[0].program_allowlist_intervall | \
+             Unknown key environments[1].include_system_account)"
+        ));
+    }
+
+    #[test]
+    fn known_keys() {
+        let (_, warnings) =
+            Config::read_with_warnings("test/fixtures/configs/multi-env-typed.json")
+                .expect("should deserialize config");
+        assert!(warnings.is_empty());
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use log::warn;
+use rdkafka::ClientConfig;
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    allowlist::Allowlist,
+    config::is_secret,
+    env_config::{EnvConfigKafka, EnvConfigLocal},
+    topic_template, Config, EnvConfig,
+};
+
+/// Maximum length of a Kafka topic name.
+const MAX_TOPIC_LENGTH: usize = 249;
+
+impl Config {
+    /// Validates the config when the plugin loads it. The mistakes only fail the load if
+    /// [strict_config] is set, otherwise a warning is logged for each of them.
+    pub fn validate_on_load(&self) -> Result<(), Vec<String>> {
+        match self.validate(false) {
+            Err(errors) if !self.strict_config => {
+                for error in errors {
+                    warn!("Invalid config: {}", error);
+                }
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Checks the config for mistakes that would otherwise only surface once the validator
+    /// loads the plugin and returns a readable message for each of them.
+    /// The program allowlist and denylist of each environment are fetched from their URLs if
+    /// [check_allowlist_urls] is set.
+    pub fn validate(&self, check_allowlist_urls: bool) -> Result<(), Vec<String>> {
+        let mut errors = vec![];
+        validate_overrides(
+            "update_account_topic_overrides",
+            &self.update_account_topic_overrides,
+            &mut errors,
+        );
+        // Local environments use the topics as paths, thus only Kafka topics are validated
+        if self
+            .environments
+            .iter()
+            .any(|env| matches!(env, EnvConfig::Kafka(_)))
+        {
+            for (field, topic, per_owner) in [
+                ("startup_account_topic", &self.startup_account_topic, true),
+                ("block_metadata_topic", &self.block_metadata_topic, false),
+                ("entry_topic", &self.entry_topic, false),
+                ("end_of_startup_topic", &self.end_of_startup_topic, false),
+                ("account_created_topic", &self.account_created_topic, true),
+            ] {
+                validate_topic(field, topic, per_owner, &mut errors);
+            }
+            validate_pubkeys("owner_labels", self.owner_labels.keys(), &mut errors);
+            for label in self.owner_labels.values() {
+                validate_topic("owner_labels", label, false, &mut errors);
+            }
+        }
+
+        for (idx, env) in self.environments.iter().enumerate() {
+            let mut env_errors = vec![];
+            let name = match env {
+                EnvConfig::Kafka(env) => {
+                    self.validate_kafka_env(env, check_allowlist_urls, &mut env_errors);
+                    &env.name
+                }
+                EnvConfig::Local(env) => {
+                    validate_local_env(env, check_allowlist_urls, &mut env_errors);
+                    &env.name
+                }
+            };
+            let name = if name.is_empty() {
+                format!("#{idx}")
+            } else {
+                name.clone()
+            };
+            errors.extend(
+                env_errors
+                    .into_iter()
+                    .map(|err| format!("{err} in {name} environment")),
+            );
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_kafka_env(
+        &self,
+        env: &EnvConfigKafka,
+        check_allowlist_urls: bool,
+        errors: &mut Vec<String>,
+    ) {
+        validate_pubkeys("program_allowlist", &env.program_allowlist, errors);
+        validate_pubkeys("program_denylist", &env.program_denylist, errors);
+        if let Some(overrides) = &env.update_account_topic_overrides {
+            validate_overrides("update_account_topic_overrides", overrides, errors);
+        }
+
+        if env.program_allowlist_url.is_empty()
+            && env.program_allowlist.is_empty()
+            && env.program_denylist_url.is_empty()
+            && env.program_denylist.is_empty()
+        {
+            errors.push(
+                "Need to provide a program_allowlist, a program_allowlist_url or a \
+                 program_denylist"
+                    .into(),
+            );
+        }
+        if !(env.program_allowlist_url.is_empty() && env.program_denylist_url.is_empty())
+            && env.program_allowlist_slot_interval == 0
+        {
+            errors.push("program_allowlist_slot_interval must be greater than 0".into());
+        }
+        if check_allowlist_urls {
+            for (field, url) in [
+                ("program_allowlist_url", &env.program_allowlist_url),
+                ("program_denylist_url", &env.program_denylist_url),
+            ] {
+                validate_url(field, url, &env.program_allowlist_auth, errors);
+            }
+        }
+
+        let topics = env.topics(self);
+        for (field, topic, per_owner) in [
+            ("update_account_topic", &topics.update_account_topic, true),
+            ("slot_status_topic", &topics.slot_status_topic, false),
+            ("transaction_topic", &topics.transaction_topic, false),
+            ("vote_transaction_topic", &env.vote_transaction_topic, false),
+            (
+                "failed_transaction_topic",
+                &env.failed_transaction_topic,
+                false,
+            ),
+        ] {
+            validate_topic(field, topic, per_owner, errors);
+        }
+        for topic in topics.update_account_topic_overrides.values() {
+            validate_topic("update_account_topic_overrides", topic, true, errors);
+        }
+
+        // librdkafka rejects unknown properties and invalid values when they are set
+        let mut config = ClientConfig::new();
+        for (k, v) in env.kafka.iter() {
+            config.set(k, v);
+        }
+        if let Err(err) = config.create_native_config() {
+            // librdkafka includes the invalid value in its error
+            let err = env
+                .kafka
+                .iter()
+                .filter(|(k, v)| is_secret(k) && !v.is_empty())
+                .fold(err.to_string(), |err, (_, v)| err.replace(v, "<redacted>"));
+            errors.push(format!("Invalid kafka config ({err})"));
+        }
+    }
+}
+
+fn validate_local_env(env: &EnvConfigLocal, check_allowlist_urls: bool, errors: &mut Vec<String>) {
+    validate_pubkeys("program_allowlist", &env.program_allowlist, errors);
+    validate_pubkeys("program_denylist", &env.program_denylist, errors);
+    if check_allowlist_urls {
+        validate_url(
+            "program_denylist_url",
+            &env.program_denylist_url,
+            "",
+            errors,
+        );
+    }
+    if let Some(overrides) = &env.update_account_topic_overrides {
+        validate_overrides("update_account_topic_overrides", overrides, errors);
+    }
+    if env.url.is_empty() {
+        errors.push("Need to provide a url".into());
+    }
+}
+
+/// Fetches the program list from [url] unless it is empty.
+fn validate_url(field: &str, url: &str, auth: &str, errors: &mut Vec<String>) {
+    if url.is_empty() {
+        return;
+    }
+    if let Err(err) = Allowlist::fetch_remote_allowlist(url, auth) {
+        errors.push(format!("{field} is unreachable ({err})"));
+    }
+}
+
+fn validate_overrides(
+    field: &str,
+    overrides: &HashMap<String, HashSet<String>>,
+    errors: &mut Vec<String>,
+) {
+    for accounts in overrides.values() {
+        validate_pubkeys(field, accounts, errors);
+    }
+}
+
+fn validate_pubkeys<'a>(
+    field: &str,
+    pubkeys: impl IntoIterator<Item = &'a String>,
+    errors: &mut Vec<String>,
+) {
+    for pubkey in pubkeys {
+        if let Err(err) = Pubkey::from_str(pubkey) {
+            errors.push(format!("Invalid pubkey '{pubkey}' in {field} ({err})"));
+        }
+    }
+}
+
+/// Validates [topic] against the naming rules of Kafka. Empty topics are disabled and valid.
+/// Placeholders of topic templates are validated, but not their expanded values. Placeholders
+/// of the owner are only valid if [per_owner] is set.
+fn validate_topic(field: &str, topic: &str, per_owner: bool, errors: &mut Vec<String>) {
+    if topic.is_empty() {
+        return;
+    }
+    match topic_template::parse(topic) {
+        Err(err) => {
+            errors.push(format!("Invalid topic '{topic}' in {field} ({err})"));
+            return;
+        }
+        Ok(true) if !per_owner => {
+            errors.push(format!(
+                "Invalid topic '{topic}' in {field} (the owner is only known for account topics)"
+            ));
+            return;
+        }
+        Ok(_) => {}
+    }
+    let name = topic_template::strip(topic);
+    if name == "." || name == ".." {
+        errors.push(format!("Invalid topic '{topic}' in {field}"));
+    } else if name.len() > MAX_TOPIC_LENGTH {
+        errors.push(format!(
+            "Invalid topic '{topic}' in {field} (longer than {MAX_TOPIC_LENGTH} characters)"
+        ));
+    } else if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+    {
+        errors.push(format!(
+            "Invalid topic '{topic}' in {field} (contains '{c}')"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_config() {
+        let config = Config::read_from("test/fixtures/configs/single-env-topic-overrides.json")
+            .expect("should deserialize config");
+        assert_eq!(config.validate(false), Ok(()));
+    }
+
+    #[test]
+    fn valid_topic_templates() {
+        let config = Config::read_from("test/fixtures/configs/topic-templates.json")
+            .expect("should deserialize config");
+        assert_eq!(config.validate(false), Ok(()));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let config = Config::read_from("test/fixtures/configs/invalid-config.json")
+            .expect("should deserialize config");
+        let mut errors = config.validate(false).unwrap_err();
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                "Invalid kafka config (Client config error: No such configuration property: \
+                 \"bootstrap.server\" bootstrap.server dev-kafka:9092) in dev environment",
+                "Invalid pubkey 'not-a-pubkey' in update_account_topic_overrides \
+                 (Invalid Base58 string)",
+                "Invalid topic 'geyser.{cluster}.{entry}' in entry_topic (unknown placeholder \
+                 {entry}, expected one of {cluster}, {event}, {owner}, {owner_label})",
+                "Invalid topic 'geyser.{cluster}.{owner}.votes' in vote_transaction_topic (the \
+                 owner is only known for account topics) in dev environment",
+                "Invalid topic 'geyser/slots' in slot_status_topic (contains '/') in dev \
+                 environment",
+                "Need to provide a program_allowlist, a program_allowlist_url or a \
+                 program_denylist in #1 environment",
+                "Need to provide a url in local environment",
+                "program_allowlist_slot_interval must be greater than 0 in dev environment",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_config_on_load() {
+        let mut config = Config::read_from("test/fixtures/configs/invalid-config.json")
+            .expect("should deserialize config");
+        assert_eq!(config.validate_on_load(), Ok(()));
+
+        config.strict_config = true;
+        assert_eq!(config.validate_on_load().unwrap_err().len(), 8);
+    }
+}